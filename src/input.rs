@@ -0,0 +1,255 @@
+// Keyboard and pointer input for Xilem Sudoku
+// (c) S. Salewski 2026
+
+//! A view that passes the pointer and key presses reaching it on to the app.
+//!
+//! Xilem's views report finished interactions, such as a button click, but
+//! not when a press began, nor any raw keys. `input_area` wraps a view, lays
+//! it out unchanged and reports pointer buttons going down and up over it.
+//! With `with_keys` it also takes keyboard focus when clicked and reports
+//! every key pressed while it, or a widget inside it, has that focus.
+
+use std::marker::PhantomData;
+
+use masonry::accesskit::{Node, Role};
+use masonry::core::keyboard::{Key, KeyState};
+use masonry::core::{
+    AccessCtx, ChildrenIds, EventCtx, LayoutCtx, MeasureCtx, PaintCtx, PointerButton,
+    PointerButtonEvent, PointerEvent, PropertiesMut, PropertiesRef, RegisterCtx, TextEvent, Widget,
+    WidgetMut, WidgetPod,
+};
+use masonry::kurbo::{Axis, Point, Size};
+use masonry::layout::{LayoutSize, LenReq};
+use masonry::vello::Scene;
+use xilem::core::{MessageContext, MessageResult, Mut, View, ViewId, ViewMarker};
+use xilem::{Pod, ViewCtx, WidgetView};
+
+/// What an `input_area` reports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    /// A pointer button went down over the area.
+    Down(Option<PointerButton>),
+    /// A pointer button was released over the area, wherever it went down.
+    Up(Option<PointerButton>),
+    /// A key went down.
+    Key(Key),
+}
+
+/// Widget behind `input_area`: lays out its child as it is and reports the
+/// pointer and key events passing through.
+pub struct InputArea {
+    child: WidgetPod<dyn Widget>,
+    /// Take keyboard focus and report keys.
+    keys: bool,
+}
+
+impl InputArea {
+    fn child_mut<'t>(this: &'t mut WidgetMut<'_, Self>) -> WidgetMut<'t, dyn Widget> {
+        this.ctx.get_mut(&mut this.widget.child)
+    }
+
+    fn set_keys(this: &mut WidgetMut<'_, Self>, keys: bool) {
+        this.widget.keys = keys;
+    }
+}
+
+impl Widget for InputArea {
+    type Action = Input;
+
+    fn on_pointer_event(
+        &mut self,
+        ctx: &mut EventCtx<'_>,
+        _props: &mut PropertiesMut<'_>,
+        event: &PointerEvent,
+    ) {
+        // The pointer isn't captured, so a release is reported by the area
+        // it happens over, which need not be the one the press began in.
+        match event {
+            PointerEvent::Down(PointerButtonEvent { button, .. }) => {
+                // Keys reach the focused widget; a press nothing else claimed
+                // makes that this one.
+                if self.keys && !ctx.is_handled() {
+                    ctx.request_focus();
+                }
+                ctx.submit_action::<Self>(Input::Down(*button));
+            }
+            PointerEvent::Up(PointerButtonEvent { button, .. }) => {
+                ctx.submit_action::<Self>(Input::Up(*button));
+            }
+            _ => {}
+        }
+    }
+
+    fn on_text_event(
+        &mut self,
+        ctx: &mut EventCtx<'_>,
+        _props: &mut PropertiesMut<'_>,
+        event: &TextEvent,
+    ) {
+        if let TextEvent::Keyboard(event) = event
+            && self.keys
+            && event.state == KeyState::Down
+        {
+            ctx.submit_action::<Self>(Input::Key(event.key.clone()));
+        }
+    }
+
+    fn accepts_focus(&self) -> bool {
+        self.keys
+    }
+
+    fn register_children(&mut self, ctx: &mut RegisterCtx<'_>) {
+        ctx.register_child(&mut self.child);
+    }
+
+    fn measure(
+        &mut self,
+        ctx: &mut MeasureCtx<'_>,
+        _props: &PropertiesRef<'_>,
+        axis: Axis,
+        len_req: LenReq,
+        cross_length: Option<f64>,
+    ) -> f64 {
+        let context_size = LayoutSize::maybe(axis.cross(), cross_length);
+        ctx.compute_length(
+            &mut self.child,
+            len_req.into(),
+            context_size,
+            axis,
+            cross_length,
+        )
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx<'_>, _props: &PropertiesRef<'_>, size: Size) {
+        ctx.run_layout(&mut self.child, size);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+    }
+
+    fn paint(&mut self, _ctx: &mut PaintCtx<'_>, _props: &PropertiesRef<'_>, _scene: &mut Scene) {}
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(
+        &mut self,
+        _ctx: &mut AccessCtx<'_>,
+        _props: &PropertiesRef<'_>,
+        _node: &mut Node,
+    ) {
+    }
+
+    fn children_ids(&self) -> ChildrenIds {
+        ChildrenIds::from_slice(&[self.child.id()])
+    }
+}
+
+/// Wrap `child` so that `on_input` hears the pointer presses over it.
+pub fn input_area<State, Action, V, F>(child: V, on_input: F) -> InputAreaView<V, F, State, Action>
+where
+    V: WidgetView<State, Action>,
+    F: Fn(&mut State, Input) -> Action + Send + Sync + 'static,
+{
+    InputAreaView {
+        child,
+        on_input,
+        keys: false,
+        phantom: PhantomData,
+    }
+}
+
+/// The view returned by `input_area`.
+pub struct InputAreaView<V, F, State, Action> {
+    child: V,
+    on_input: F,
+    keys: bool,
+    phantom: PhantomData<fn(State) -> Action>,
+}
+
+impl<V, F, State, Action> InputAreaView<V, F, State, Action> {
+    /// Also report the keys pressed while the area, or a widget inside it,
+    /// has keyboard focus; a click the widgets inside ignore focuses it.
+    pub fn with_keys(mut self) -> Self {
+        self.keys = true;
+        self
+    }
+}
+
+const CHILD_VIEW_ID: ViewId = ViewId::new(0);
+
+impl<V, F, State, Action> ViewMarker for InputAreaView<V, F, State, Action> {}
+
+impl<V, F, State, Action> View<State, Action, ViewCtx> for InputAreaView<V, F, State, Action>
+where
+    State: 'static,
+    Action: 'static,
+    V: WidgetView<State, Action>,
+    F: Fn(&mut State, Input) -> Action + Send + Sync + 'static,
+{
+    type Element = Pod<InputArea>;
+    type ViewState = V::ViewState;
+
+    fn build(&self, ctx: &mut ViewCtx, app_state: &mut State) -> (Self::Element, Self::ViewState) {
+        let (child, child_state) =
+            ctx.with_id(CHILD_VIEW_ID, |ctx| self.child.build(ctx, app_state));
+        let widget = InputArea {
+            child: child.new_widget.erased().to_pod(),
+            keys: self.keys,
+        };
+        (
+            ctx.with_action_widget(|ctx| ctx.create_pod(widget)),
+            child_state,
+        )
+    }
+
+    fn rebuild(
+        &self,
+        prev: &Self,
+        view_state: &mut Self::ViewState,
+        ctx: &mut ViewCtx,
+        mut element: Mut<'_, Self::Element>,
+        app_state: &mut State,
+    ) {
+        if self.keys != prev.keys {
+            InputArea::set_keys(&mut element, self.keys);
+        }
+        ctx.with_id(CHILD_VIEW_ID, |ctx| {
+            let child = InputArea::child_mut(&mut element);
+            self.child
+                .rebuild(&prev.child, view_state, ctx, child.downcast(), app_state);
+        });
+    }
+
+    fn teardown(
+        &self,
+        view_state: &mut Self::ViewState,
+        ctx: &mut ViewCtx,
+        mut element: Mut<'_, Self::Element>,
+    ) {
+        ctx.with_id(CHILD_VIEW_ID, |ctx| {
+            let child = InputArea::child_mut(&mut element);
+            self.child.teardown(view_state, ctx, child.downcast());
+        });
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        message: &mut MessageContext,
+        mut element: Mut<'_, Self::Element>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        match message.take_first() {
+            Some(CHILD_VIEW_ID) => {
+                let child = InputArea::child_mut(&mut element);
+                self.child
+                    .message(view_state, message, child.downcast(), app_state)
+            }
+            None => match message.take_message::<Input>() {
+                Some(input) => MessageResult::Action((self.on_input)(app_state, *input)),
+                None => MessageResult::Stale,
+            },
+            Some(_) => MessageResult::Stale,
+        }
+    }
+}
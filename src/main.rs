@@ -2,419 +2,5828 @@
 // (c) S. Salewski 2025, 2026
 // 13-FEB-2026
 
-use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use rand::rng;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+
+use masonry::core::PointerButton;
+use masonry::core::keyboard::{Key, NamedKey};
 use masonry::dpi::LogicalSize;
-use masonry::parley::FontStack;
+use masonry::kurbo::Size;
+use masonry::parley::{FontStack, FontWeight};
 use masonry::layout::Length;
 use masonry::layout::AsUnit;
 use xilem::masonry::theme::{DEFAULT_GAP};
 //use masonry::properties::types::{AsUnit, Length};
 
+use tokio::sync::oneshot;
 use tokio::time;
 use winit::error::EventLoopError;
 
 use xilem::core::fork;
+use xilem::core::one_of::Either;
 use xilem::style::Style; // required for style extension methods
 use xilem::view::{
-    FlexExt, FlexSpacer, GridExt, button, flex_col, flex_row, grid, label, sized_box, slider, task,
-    text_button,
+    FlexExt, FlexSpacer, GridExt, GridParams, MainAxisAlignment, checkbox, flex_col, flex_row,
+    grid, label, portal, resize_observer, sized_box, slider, task, task_raw, text_button,
+    text_input, zstack,
 };
 use xilem::{Color, EventLoop, TextAlign, WidgetView, WindowOptions, Xilem};
 //use xilem_core::Edit;
 
+mod input;
+mod sound;
 mod sudoku;
 
+use input::{Input, input_area};
+use sound::Effect;
+use sudoku::{DEFAULT_BLOCK_SIDE, MAX_SIDE, Variant};
+
 const DEFAULT_DIFFICULTY: f64 = sudoku::SUGGESTED_DIFFICULTY_LEVEL as f64;
 
 // Board geometry
-const SIDE: usize = 9;
-const CELL_COUNT: usize = SIDE * SIDE;
-const BLOCK_SIDE: usize = 3;
-const BOARD_BLOCKS: usize = SIDE / BLOCK_SIDE;
+/// Board sizes offered in the UI, by block side: 4×4, 9×9 and 16×16.
+const BOARD_SIZES: [usize; 3] = sudoku::BLOCK_SIDES;
+/// Text sizes below are tuned for 9 cells per row.
+const REFERENCE_SIDE: f32 = 9.0;
+/// Upper bound for enlarging text on boards with fewer cells per row.
+const MAX_TEXT_SCALE: f32 = 2.0;
+/// Range of the player's font scale slider.
+const MIN_FONT_SCALE: f64 = 0.5;
+const MAX_FONT_SCALE: f64 = 2.0;
+/// Range of the zoom slider for boards larger than the classic one.
+const MIN_BOARD_ZOOM: f64 = 1.0;
+const MAX_BOARD_ZOOM: f64 = 3.0;
+/// Smallest window side the layout fits into, and the side of a first window.
+const MIN_WINDOW_SIDE: f64 = 600.0;
+const DEFAULT_WINDOW_SIDE: f64 = 700.0;
+/// How long the window size must stay put before it is written to the settings.
+const WINDOW_SAVE_DELAY: Duration = Duration::from_millis(500);
 
 const GRID_GAP: Length = Length::const_px(3.0);
-const GAP: Length = Length::const_px(4.0);
+const GAP_SIZE: f64 = 4.0;
+const GAP: Length = Length::const_px(GAP_SIZE);
+/// Shares of the play area's length taken by the board and the number pad.
+const BOARD_FLEX: f64 = 9.0;
+const PAD_FLEX: f64 = 1.0;
+/// Frame around each block, so the block structure stands out from the cell grid.
+const BLOCK_BORDER_WIDTH: f64 = 2.0;
+/// Outline of the selected cell; every cell reserves the width, so selecting
+/// one doesn't shift its digit.
+const FOCUS_RING_WIDTH: f64 = 2.0;
 
 // Colors
 const SOURCE_BG: Color = Color::from_rgb8(0x3a, 0x3a, 0x9a);
 const CLUE_TEXT_COLOR: Color = Color::from_rgb8(0x7f, 0x7f, 0x7f);
+/// Clues are set a little heavier, so they can't pass for a faint guess.
+const CLUE_FONT_WEIGHT: FontWeight = FontWeight::SEMI_BOLD;
 const GUESS_TEXT_COLOR: Color = Color::from_rgb8(0xff, 0xff, 0xff);
 const FAIL_TEXT_COLOR: Color = Color::from_rgb8(0xff, 0x00, 0x00);
 const SUDOKU_BACKGROUND_COLOR: Color = Color::from_rgb8(0x33, 0x33, 0x33);
 const SUDOKU_HIGHLIGHT_COLOR: Color = Color::from_rgb8(0x28, 0x28, 0x28);
+/// Shades of the mini-map for empty cells; clues and guesses take their text colors.
+const MINIMAP_EMPTY_COLOR: Color = Color::from_rgb8(0x4a, 0x4a, 0x4a);
+/// Side of one mini-map dot and the gap between dots, in pixels.
+const MINIMAP_DOT: f64 = 4.0;
+const MINIMAP_GAP: Length = Length::const_px(1.0);
 const SELECTED_BACKGROUND_COLOR: Color = Color::from_rgb8(0x66, 0x66, 0x66);
+const SAME_DIGIT_BACKGROUND_COLOR: Color = Color::from_rgb8(0x4a, 0x4a, 0x5e);
+const PEN_FITS_BACKGROUND_COLOR: Color = Color::from_rgb8(0x34, 0x48, 0x3c);
+const REVEALED_TEXT_COLOR: Color = Color::from_rgb8(0x6f, 0xb3, 0xff);
+const NOTE_TEXT_COLOR: Color = Color::from_rgb8(0x9f, 0x9f, 0x9f);
+const COORDINATE_TEXT_COLOR: Color = Color::from_rgb8(0x6f, 0x6f, 0x6f);
+const HINT_BACKGROUND_COLOR: Color = Color::from_rgb8(0x2e, 0x6b, 0x3a);
+/// Outline of the cells a failed guess repeats the digit of.
+const CONFLICT_PEER_COLOR: Color = Color::from_rgb8(0xe0, 0x4a, 0x4a);
+/// Cells an explanation refers to; its target cell takes `HINT_BACKGROUND_COLOR`.
+const EXPLAIN_BACKGROUND_COLOR: Color = Color::from_rgb8(0x2a, 0x44, 0x5c);
+const DIAGONAL_BACKGROUND_COLOR: Color = Color::from_rgb8(0x3b, 0x33, 0x45);
+const BLOCK_BORDER_COLOR: Color = Color::from_rgb8(0x8f, 0x8f, 0x8f);
+const FOCUS_RING_COLOR: Color = Color::from_rgb8(0xf0, 0xe0, 0x60);
+const FLASH_CORRECT_COLOR: Color = Color::from_rgb8(0x2f, 0x9e, 0x44);
+const FLASH_WRONG_COLOR: Color = Color::from_rgb8(0xb0, 0x20, 0x20);
+const COMPLETION_COLOR: Color = Color::from_rgb8(0xc9, 0xa2, 0x27);
+/// Clock colors while the player is ahead of, or behind, their best pace.
+const PACE_AHEAD_COLOR: Color = Color::from_rgb8(0x5f, 0xd0, 0x6f);
+const PACE_BEHIND_COLOR: Color = Color::from_rgb8(0xf0, 0xa0, 0x30);
+/// Side length of a digit button in the touch picker.
+const PICKER_BUTTON_SIZE: f64 = 48.0;
+/// Shade of the empty cells with a single candidate in the heatmap.
+const HEATMAP_COLOR: Color = Color::from_rgb8(0xc8, 0x64, 0x1e);
+
+/// Shown next to conflicting digits in colorblind mode.
+const CONFLICT_GLYPH: &str = "⚠";
+
+// Text sizes
+const CELL_TEXT_SIZE: f32 = 24.0;
+const NOTE_TEXT_SIZE: f32 = 10.0;
+const CENTER_NOTE_TEXT_SIZE: f32 = 14.0;
+const BADGE_TEXT_SIZE: f32 = 11.0;
+
+/// Named difficulty levels offered as buttons; the slider reaches all levels.
+const DIFFICULTY_PRESETS: [(&str, u8); 4] = [
+    ("Easy", 1),
+    ("Medium", 3),
+    ("Hard", 5),
+    ("Expert", 7),
+];
+/// Mistake limits offered in the UI; `None` means unlimited.
+const FAIL_LIMIT_PRESETS: [Option<i32>; 4] = [None, Some(3), Some(5), Some(10)];
+/// Clue counts offered for new classic puzzles; `None` leaves it to the
+/// difficulty level.
+const CLUE_TARGET_PRESETS: [Option<usize>; 4] = [None, Some(32), Some(28), Some(24)];
+/// Timer modes offered in the UI: counting up, or down from a target time.
+const TIMER_PRESETS: [Timer; 5] = [
+    Timer::CountUp,
+    Timer::CountDown(Duration::from_secs(5 * 60)),
+    Timer::CountDown(Duration::from_secs(10 * 60)),
+    Timer::CountDown(Duration::from_secs(20 * 60)),
+    Timer::CountDown(Duration::from_secs(30 * 60)),
+];
+
+const SAVE_FILE_NAME: &str = "game.json";
+const STATS_FILE_NAME: &str = "stats.json";
+/// Number of recent games kept per difficulty level.
+const HISTORY_LEN: usize = 10;
+/// Level of the puzzle of the day, the same for every player.
+const DAILY_LEVEL: u8 = sudoku::SUGGESTED_DIFFICULTY_LEVEL;
+const SETTINGS_FILE_NAME: &str = "settings.json";
+const HINT_HIGHLIGHT_MS: u64 = 1000;
+/// Longest gap between two clicks on a cell that still makes a double click.
+const DOUBLE_CLICK_MS: u64 = 400;
+/// Shortest press on a cell that opens the digit picker rather than counting as a tap.
+const LONG_PRESS_MS: u64 = 500;
+/// How long a cell flashes after a digit is placed in it.
+const FLASH_MS: u64 = 300;
+/// How long a row, column or block glows after its last cell is solved.
+const COMPLETION_MS: u64 = 600;
+/// Refresh interval while a flash fades.
+const FLASH_FRAME: Duration = Duration::from_millis(20);
+/// Most playing time a single timer tick may add: twice the one-second tick
+/// interval. Longer gaps mean the machine was suspended, not that anyone played.
+const MAX_TICK_DELTA: Duration = Duration::from_secs(2);
+/// Attempts to deliver a timer tick before the timer task gives up.
+const TICK_SEND_ATTEMPTS: u32 = 5;
+/// Pause before retrying a failed tick; it doubles with each further failure.
+const TICK_RETRY_DELAY: Duration = Duration::from_millis(50);
+/// Seconds added to the clock for each use of "Check".
+const CHECK_PENALTY_SECS: u64 = 30;
+
+// Score of a solved game: base points for the difficulty, less penalties for
+// the time taken and the help needed. Never below zero.
+const SCORE_BASE: i64 = 1000;
+const SCORE_PER_LEVEL: i64 = 500;
+const SCORE_PER_SECOND: i64 = 1;
+const SCORE_PER_FAIL: i64 = 50;
+const SCORE_PER_HINT: i64 = 100;
+const SCORE_PER_CHECK: i64 = 75;
+
+/// One recorded board edit.
+#[derive(Clone, Debug, PartialEq)]
+struct Edit {
+    index: usize,
+    old: i8,
+    new: i8,
+    /// Peer cells whose note for `new` was struck off by placing it.
+    struck: Vec<usize>,
+    /// Corner and center notes of the cell itself before the edit, which
+    /// placing a digit wipes.
+    notes: [bool; MAX_SIDE],
+    center_notes: [bool; MAX_SIDE],
+}
+
+/// A saved position to come back to after trying out a risky line of play.
+#[derive(Clone, Debug, PartialEq)]
+struct Bookmark {
+    board: Vec<i8>,
+    notes: Vec<[bool; MAX_SIDE]>,
+    center_notes: Vec<[bool; MAX_SIDE]>,
+    revealed: Vec<bool>,
+}
+
+/// Which cells light up around the selected cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum HighlightMode {
+    /// Its row and column, and cells with the same digit.
+    RowCol,
+    /// Its row, column and block (and diagonals in X-Sudoku), and cells with the same digit.
+    #[default]
+    RowColBlock,
+    /// Only cells with the same digit.
+    SameDigit,
+    /// Nothing but the selected cell itself.
+    None,
+}
+
+impl HighlightMode {
+    const ALL: [Self; 4] = [Self::RowCol, Self::RowColBlock, Self::SameDigit, Self::None];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::RowCol => "Row+Col",
+            Self::RowColBlock => "Row+Col+Block",
+            Self::SameDigit => "Same digit",
+            Self::None => "Off",
+        }
+    }
+}
+
+/// What the digit buttons do to the selected cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum NoteMode {
+    /// Digits are placed as guesses.
+    #[default]
+    Off,
+    /// Digits toggle corner notes, laid out like a block of the board.
+    Corner,
+    /// Digits toggle center notes, written larger across the middle of the cell.
+    Center,
+}
+
+impl NoteMode {
+    const ALL: [Self; 3] = [Self::Off, Self::Corner, Self::Center];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Corner => "Corner",
+            Self::Center => "Center",
+        }
+    }
+}
+
+/// Where the number pad sits relative to the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum PadPosition {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl PadPosition {
+    const ALL: [Self; 4] = [Self::Top, Self::Bottom, Self::Left, Self::Right];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Top => "Top",
+            Self::Bottom => "Bottom",
+            Self::Left => "Left",
+            Self::Right => "Right",
+        }
+    }
+
+    /// Beside the board, so the digits stack vertically.
+    fn is_beside(self) -> bool {
+        matches!(self, Self::Left | Self::Right)
+    }
+}
+
+/// How digits are drawn on the board, the pad and the picker. Only the
+/// display changes; the game keeps working with the numbers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum GlyphSet {
+    /// 1–9, then A–G on 16×16 boards.
+    #[default]
+    Digits,
+    /// A–I, or A–P on 16×16 boards.
+    Letters,
+    Shapes,
+}
+
+/// One shape per digit, enough for 16×16 boards.
+const SHAPES: [char; 16] = [
+    '●', '■', '▲', '◆', '★', '♥', '♣', '♠', '✚', '○', '□', '△', '◇', '☆', '♡', '✖',
+];
+
+impl GlyphSet {
+    const ALL: [Self; 3] = [Self::Digits, Self::Letters, Self::Shapes];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Digits => "Digits",
+            Self::Letters => "Letters",
+            Self::Shapes => "Shapes",
+        }
+    }
+
+    /// The glyph shown for `digit`, from 1 up to the side of the board.
+    fn glyph(self, digit: i8) -> char {
+        match self {
+            Self::Digits => sudoku::digit_char(digit),
+            Self::Letters => char::from(b'A' + digit as u8 - 1),
+            Self::Shapes => SHAPES[digit as usize - 1],
+        }
+    }
+}
+
+/// What the clock shows: the time played, or in a challenge the time left
+/// before the game is lost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Timer {
+    #[default]
+    CountUp,
+    CountDown(Duration),
+}
+
+impl Timer {
+    fn label(self) -> String {
+        match self {
+            Self::CountUp => "Up".to_string(),
+            Self::CountDown(limit) => format_time(limit.as_secs()),
+        }
+    }
+}
+
+/// Keyboard input already decoded into board commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyInput {
+    Digit(i8),
+    Clear,
+    Up,
+    Down,
+    Left,
+    Right,
+    Help,
+    Escape,
+}
 
-const TIMER_TICK_MS: u64 = 50;
+/// The next logical step, as shown by "Explain".
+#[derive(Clone, Debug, PartialEq)]
+struct Explanation {
+    text: String,
+    /// Cell the step fills, if one was found.
+    target: Option<usize>,
+    /// Cells the reasoning refers to.
+    cells: Vec<usize>,
+}
 
 // --- Small helpers for board indexing ---------------------------------------------------------
 
-#[inline]
-fn row_of(index: usize) -> usize {
-    index / SIDE
+/// Size of the board: `side`×`side` cells in `block_side`×`block_side` blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Geometry {
+    block_side: usize,
+}
+
+impl Geometry {
+    #[inline]
+    fn side(self) -> usize {
+        self.block_side * self.block_side
+    }
+
+    #[inline]
+    fn cell_count(self) -> usize {
+        self.side() * self.side()
+    }
+
+    #[inline]
+    fn row_of(self, index: usize) -> usize {
+        index / self.side()
+    }
+
+    #[inline]
+    fn col_of(self, index: usize) -> usize {
+        index % self.side()
+    }
+
+    #[inline]
+    fn row_start(self, index: usize) -> usize {
+        self.row_of(index) * self.side()
+    }
+
+    #[inline]
+    fn block_origin(self, index: usize) -> usize {
+        let block_row = self.row_of(index) / self.block_side;
+        let block_col = self.col_of(index) / self.block_side;
+        block_row * self.side() * self.block_side + block_col * self.block_side
+    }
+
+    #[inline]
+    fn on_diagonal(self, index: usize) -> bool {
+        let (row, col) = (self.row_of(index), self.col_of(index));
+        row == col || row + col == self.side() - 1
+    }
+
+    /// Scale for text sizes, so digits fill the cells of every board size alike.
+    fn text_scale(self) -> f32 {
+        (REFERENCE_SIDE / self.side() as f32).min(MAX_TEXT_SCALE)
+    }
+}
+
+// --- Statistics -------------------------------------------------------------------------------
+
+/// Win statistics kept across sessions.
+#[derive(Default, Serialize, Deserialize)]
+struct Stats {
+    /// Number of solved games, assisted or not.
+    games_completed: u32,
+    /// Consecutive games solved without assistance.
+    streak: u32,
+    /// Best unassisted time in seconds, per difficulty level.
+    best_times: BTreeMap<u8, u64>,
+    /// The last `HISTORY_LEN` solved games per difficulty level, oldest first.
+    #[serde(default)]
+    history: BTreeMap<u8, VecDeque<Completion>>,
+    /// Dates (yyyymmdd) of solved daily puzzles, with the best unassisted time for each.
+    #[serde(default)]
+    daily: BTreeMap<u32, Option<u64>>,
+}
+
+/// One solved game, as listed in the history.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Completion {
+    seconds: u64,
+    fails: i32,
+    /// Whether hints, checks or Solve were used.
+    assisted: bool,
+    /// See `AppState::score`; zero for games recorded before scores existed.
+    #[serde(default)]
+    score: i64,
+}
+
+impl Stats {
+    /// Missing or unreadable statistics start over from zero.
+    fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        write_json(path, self)
+    }
+
+    fn best_time(&self, level: u8) -> Option<u64> {
+        self.best_times.get(&level).copied()
+    }
+
+    /// Recent games at `level`, oldest first.
+    fn history(&self, level: u8) -> impl DoubleEndedIterator<Item = &Completion> {
+        self.history.get(&level).into_iter().flatten()
+    }
+
+    /// Record a solved game; returns true if it set a new best time.
+    fn record(&mut self, level: u8, game: Completion) -> bool {
+        self.games_completed += 1;
+        let history = self.history.entry(level).or_default();
+        if history.len() == HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(game);
+
+        if game.assisted {
+            self.streak = 0;
+            return false;
+        }
+
+        self.streak += 1;
+        let new_record = self.best_time(level).is_none_or(|best| game.seconds < best);
+        if new_record {
+            self.best_times.insert(level, game.seconds);
+        }
+        new_record
+    }
+
+    /// Record a solved puzzle of the day `date`; assisted games mark it solved,
+    /// but don't set its best time.
+    fn record_daily(&mut self, date: u32, game: Completion) {
+        let best = self.daily.entry(date).or_default();
+        if !game.assisted && best.is_none_or(|best| game.seconds < best) {
+            *best = Some(game.seconds);
+        }
+    }
+
+    fn daily_solved(&self, date: u32) -> bool {
+        self.daily.contains_key(&date)
+    }
+
+    fn daily_best(&self, date: u32) -> Option<u64> {
+        self.daily.get(&date).copied().flatten()
+    }
+}
+
+/// Today's date in UTC as yyyymmdd, so every player gets the same daily puzzle.
+fn today() -> u32 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400);
+    date_of_day(days)
+}
+
+/// The Gregorian date, as yyyymmdd, `days` days after 1970-01-01.
+fn date_of_day(days: u64) -> u32 {
+    // Count from 0000-03-01, so leap days fall at the end of the year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year * 10_000 + month * 100 + day) as u32
+}
+
+// --- Settings ---------------------------------------------------------------------------------
+
+/// Player preferences kept across sessions: how the board looks, how input
+/// and mistakes are handled, and how new puzzles are generated.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    /// Factor applied to the text of the board and the number pad.
+    font_scale: f64,
+    /// Magnification of boards larger than the classic one, which then
+    /// scroll within the play area.
+    board_zoom: f64,
+    /// Side of the board the number pad is placed on.
+    pad_position: PadPosition,
+    /// How digits are drawn.
+    glyphs: GlyphSet,
+    /// Which cells light up around the selected cell.
+    highlight_mode: HighlightMode,
+    /// Mark conflicts with a glyph as well as color, for color vision deficiency.
+    colorblind: bool,
+    /// Placing a digit removes it from the notes of the cell's peers.
+    auto_strike: bool,
+    /// After a digit is placed, the selection moves on to the next empty cell.
+    auto_advance: bool,
+    /// Entering the digit a cell already holds takes it out again; otherwise
+    /// that does nothing.
+    retype_clears: bool,
+    /// Only digits matching the solution may be placed; others are turned down.
+    strict: bool,
+    /// Flag a digit that differs from the solution as a mistake at once, even
+    /// when it repeats nothing in its row, column or block yet.
+    instant_wrong: bool,
+    /// Selecting another cell erases the last failed guess; otherwise it stays,
+    /// marked red, until the player clears it.
+    auto_clear_fail: bool,
+    /// Paper-like play: no conflict marks, mistakes or feedback on placements.
+    /// Only the finished board is checked against the solution.
+    freeform: bool,
+    /// Shade empty cells by their number of candidates, in place of the
+    /// selection highlight.
+    heatmap: bool,
+    /// Mistakes allowed before the game is lost; `None` for no limit.
+    max_fails: Option<i32>,
+    /// Generate new puzzles with 180° symmetric clues.
+    symmetric: bool,
+    /// Strip every redundant clue from new puzzles; not done on 16×16 boards,
+    /// where it takes far too long.
+    minimal: bool,
+    /// Generate new puzzles that can be solved by logic alone, without guessing.
+    logic_only: bool,
+    /// Generate new puzzles as X-Sudoku, with the diagonal rule.
+    diagonal: bool,
+    /// Exact number of clues for new classic 9×9 puzzles, in place of the
+    /// difficulty level and the other generator options.
+    clue_target: Option<usize>,
+    /// Label the rows A–I and the columns 1–9 along the board edges.
+    coordinates: bool,
+    /// Show a dot map of clues, guesses and empty cells next to the voids count.
+    minimap: bool,
+    /// Play sound effects; only heard in builds with the `sound` feature.
+    sound: bool,
+    /// Inner size of the window, in logical pixels, as last resized.
+    window_size: Option<(f64, f64)>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            font_scale: 1.0,
+            board_zoom: MIN_BOARD_ZOOM,
+            pad_position: PadPosition::default(),
+            glyphs: GlyphSet::default(),
+            highlight_mode: HighlightMode::default(),
+            colorblind: false,
+            auto_strike: true,
+            auto_advance: false,
+            retype_clears: true,
+            strict: false,
+            instant_wrong: false,
+            auto_clear_fail: true,
+            freeform: false,
+            heatmap: false,
+            max_fails: None,
+            symmetric: false,
+            minimal: false,
+            logic_only: false,
+            diagonal: false,
+            clue_target: None,
+            coordinates: false,
+            minimap: false,
+            sound: true,
+            window_size: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Missing or unreadable settings fall back to the defaults.
+    fn load(path: &Path) -> Self {
+        let mut settings: Self = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        settings.font_scale = settings.font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+        settings.board_zoom = settings.board_zoom.clamp(MIN_BOARD_ZOOM, MAX_BOARD_ZOOM);
+        settings
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        write_json(path, self)
+    }
+
+    /// The size to open the window at: the remembered one, but never below the minimum.
+    fn initial_window_size(&self) -> LogicalSize<f64> {
+        let (width, height) = self
+            .window_size
+            .unwrap_or((DEFAULT_WINDOW_SIDE, DEFAULT_WINDOW_SIDE));
+        LogicalSize::new(width.max(MIN_WINDOW_SIDE), height.max(MIN_WINDOW_SIDE))
+    }
+}
+
+// --- Worksheets -------------------------------------------------------------------------------
+
+/// Most puzzles generated for one worksheet; generation blocks the UI meanwhile.
+const MAX_WORKSHEET_PUZZLES: usize = 50;
+
+/// Inputs and outcome of the "Generate set" action, which writes a printable
+/// set of puzzles to a text file.
+struct Worksheet {
+    /// Number of puzzles, as typed.
+    count: String,
+    /// File to write, as typed.
+    path: String,
+    /// Append the solutions after the puzzles.
+    solutions: bool,
+    /// Result of the last attempt, shown next to the inputs.
+    message: Option<String>,
+}
+
+impl Default for Worksheet {
+    fn default() -> Self {
+        Self {
+            count: "10".to_string(),
+            path: "sudoku-worksheet.txt".to_string(),
+            solutions: true,
+            message: None,
+        }
+    }
+}
+
+/// Lay out `puzzles` for printing, each under a numbered header, followed by
+/// their solutions if asked for.
+fn worksheet_text(puzzles: &[sudoku::Sudoku], solutions: bool) -> String {
+    let mut text = String::new();
+    for (n, puzzle) in puzzles.iter().enumerate() {
+        let _ = writeln!(text, "Puzzle {} ({})\n\n{puzzle}", n + 1, puzzle.rate());
+    }
+    if solutions {
+        for (n, puzzle) in puzzles.iter().enumerate() {
+            let solved = sudoku::Sudoku(puzzle.1.clone(), puzzle.1.clone(), puzzle.2);
+            let _ = writeln!(text, "Solution {}\n\n{solved}", n + 1);
+        }
+    }
+    text
+}
+
+// --- Solution explorer ------------------------------------------------------------------------
+
+/// Most solutions the explorer collects for one board.
+#[cfg(feature = "solution-explorer")]
+const EXPLORER_LIMIT: usize = 100;
+
+/// Debugging aid for the generator: the distinct solutions of the board at
+/// the time the explorer was opened, stepped through one at a time.
+#[cfg(feature = "solution-explorer")]
+struct Explorer {
+    solutions: Vec<Vec<i8>>,
+    /// Index of the solution on display.
+    shown: usize,
+}
+
+#[cfg(feature = "solution-explorer")]
+impl AppState {
+    /// Search the current board, player entries included, for its solutions.
+    fn open_explorer(&mut self) {
+        let solutions = self.board_snapshot().solutions(EXPLORER_LIMIT);
+        self.explorer = Some(Explorer {
+            solutions,
+            shown: 0,
+        });
+    }
+
+    /// Show the solution `step` places further on, wrapping around at the ends.
+    fn step_explorer(&mut self, step: isize) {
+        if let Some(explorer) = &mut self.explorer
+            && !explorer.solutions.is_empty()
+        {
+            let count = explorer.solutions.len() as isize;
+            explorer.shown = (explorer.shown as isize + step).rem_euclid(count) as usize;
+        }
+    }
 }
 
-#[inline]
-fn col_of(index: usize) -> usize {
-    index % SIDE
+/// The generator level for a slider `difficulty`: rounded to the nearest
+/// step and clamped to the levels the generator knows.
+fn difficulty_level(difficulty: f64) -> u8 {
+    difficulty.round().clamp(0.0, sudoku::MAX_DIFFICULTY_LEVEL as f64) as u8
 }
 
-#[inline]
-fn row_start(index: usize) -> usize {
-    row_of(index) * SIDE
+/// A fresh puzzle at `level` on a board of `block_side`×`block_side` blocks,
+/// with the generation options of `settings`; `None` if the generator found
+/// no solved grid to start from.
+fn generate_puzzle(level: u8, block_side: usize, settings: &Settings) -> Option<sudoku::Sudoku> {
+    if let Some(clues) = settings.clue_target
+        && block_side == DEFAULT_BLOCK_SIDE
+        && !settings.diagonal
+    {
+        return sudoku::Sudoku::new_with_clues(clues);
+    }
+    let variant = if settings.diagonal {
+        Variant::Diagonal
+    } else {
+        Variant::Classic
+    };
+    let mut sudoku = if settings.logic_only {
+        sudoku::Sudoku::new_logic_only(level, block_side, variant, settings.symmetric)
+    } else if settings.symmetric {
+        sudoku::Sudoku::new_symmetric(level, block_side, variant)
+    } else {
+        sudoku::Sudoku::new(level, block_side, variant)
+    }?;
+    if settings.minimal && block_side <= DEFAULT_BLOCK_SIDE {
+        if settings.logic_only {
+            sudoku.minimize_logic_only();
+        } else {
+            sudoku.minimize();
+        }
+    }
+    Some(sudoku)
 }
 
-#[inline]
-fn block_origin(index: usize) -> usize {
-    let block_row = row_of(index) / BLOCK_SIDE;
-    let block_col = col_of(index) / BLOCK_SIDE;
-    block_row * SIDE * BLOCK_SIDE + block_col * BLOCK_SIDE
+/// Write `value` as JSON to `path`, creating the directory if needed.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let json = serde_json::to_string(value).map_err(io::Error::other)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, json)
 }
 
 // --- Application state ------------------------------------------------------------------------
 
+/// What a cell holds: a clue, a guess of the player, or nothing yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Fill {
+    Clue,
+    Guess,
+    Empty,
+}
+
+/// On-disk snapshot of a game in progress.
+///
+/// `AppState` itself is not serialized since it holds an `Instant`;
+/// the elapsed time is stored instead.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    board: Vec<i8>,
+    solution: Vec<i8>,
+    is_clue: Vec<bool>,
+    elapsed_seconds: u64,
+    fails: i32,
+    difficulty: f64,
+    /// Missing in saves from before the diagonal variant existed.
+    #[serde(default)]
+    diagonal: bool,
+    /// Missing in saves from before moves were counted.
+    #[serde(default)]
+    guesses: u32,
+    #[serde(default)]
+    correct_guesses: u32,
+    /// Date of the puzzle of the day being played, if it is one.
+    #[serde(default)]
+    daily: Option<u32>,
+    /// Target time in seconds of a countdown challenge, if it is one.
+    #[serde(default)]
+    countdown: Option<u64>,
+    /// Help taken so far, so a reloaded game is still known as assisted.
+    #[serde(default)]
+    assisted: bool,
+    #[serde(default)]
+    hints_used: u32,
+    #[serde(default)]
+    checks_used: u32,
+    #[serde(default)]
+    gave_up: bool,
+    /// Per-cell grids as in `AppState`; empty in older saves.
+    #[serde(default)]
+    revealed: Vec<bool>,
+    #[serde(default)]
+    notes: Vec<[bool; MAX_SIDE]>,
+    #[serde(default)]
+    center_notes: Vec<[bool; MAX_SIDE]>,
+    /// A lost game stays lost after a restart.
+    #[serde(default)]
+    game_over: bool,
+    #[serde(default)]
+    time_up: bool,
+}
+
 /// Full application state.
 struct AppState {
-    /// Whether the periodic timer task is active (reserved for pause/resume).
+    /// Whether the game is running; false while paused, which also stops the timer task.
     active: bool,
+    /// Size of the current board; all grids below have `geometry.cell_count()` entries.
+    geometry: Geometry,
     /// Current puzzle grid (0 = empty).
-    sudoku: [i8; CELL_COUNT],
+    sudoku: Vec<i8>,
     /// Fully solved grid used to check correctness.
-    solved: [i8; CELL_COUNT],
+    solved: Vec<i8>,
     /// Marks which cells are original clues (not editable).
-    is_clue: [bool; CELL_COUNT],
+    is_clue: Vec<bool>,
+    /// Marks cells filled by "Solve" rather than by the player.
+    revealed: Vec<bool>,
+    /// Corner notes (pencil-mark candidates) per cell; entry `d - 1` is set
+    /// when digit `d` is noted. Auto-notes and auto-strike work on these.
+    notes: Vec<[bool; MAX_SIDE]>,
+    /// Center notes per cell, kept apart from the corner notes.
+    center_notes: Vec<[bool; MAX_SIDE]>,
+    /// Whether digit buttons toggle notes, and which kind, instead of placing a guess.
+    note_mode: NoteMode,
+    /// The selected cell was picked with a right click, so digits toggle its
+    /// corner notes until another cell is selected; center notes in center mode.
+    note_selection: bool,
     /// Highlight mask (row/column/block of selected cell).
-    highlight: [bool; CELL_COUNT],
-    /// Currently selected cell index, if any.
+    highlight: Vec<bool>,
+    /// Cells holding the same digit as the selected cell, or as the pen digit.
+    highlight_same: Vec<bool>,
+    /// Empty cells the pen digit can still go into.
+    highlight_fits: Vec<bool>,
+    /// Currently selected cell index, if any. Clue cells can be selected but not edited.
     selected_cell: Option<usize>,
     /// Cell index of last failed guess, if any.
     fail: Option<usize>,
     /// Number of failed guesses.
     fails: i32,
+    /// Digits placed so far, hints included.
+    guesses: u32,
+    /// Placed digits that match the solution.
+    correct_guesses: u32,
+    /// Cell and digit of the last placement turned down by strict mode.
+    rejected: Option<(usize, i8)>,
+    /// Set once `fails` reached `max_fails`, or a countdown ran out; freezes the board.
+    game_over: bool,
+    /// The game was lost because the countdown reached zero.
+    time_up: bool,
+    /// Completion dialog; opened once when the player fills the last cell.
+    solved_dialog_open: bool,
+    /// "Discard current game?" prompt shown by "New Game" on a game in progress.
+    confirm_new_game: bool,
+    /// The game asked for at that prompt is the puzzle of the day.
+    daily_requested: bool,
+    /// The game asked for at that prompt is one at this preset level.
+    preset_requested: Option<u8>,
+    /// Cell a double click asks to reveal; it is filled in, as a hint, once
+    /// the player confirms.
+    confirm_reveal: Option<usize>,
+    /// Overlay explaining the rules, colors and controls; F1 opens it.
+    help_open: bool,
+    /// Show the list of recent games at the current difficulty.
+    history_open: bool,
+    /// Cell the touch digit picker is open for.
+    picker: Option<usize>,
+    /// Digit being dragged from the number pad, dropped into the cell the
+    /// pointer is released over.
+    dragged: Option<i8>,
+    /// Digit-first input: a pad digit is chosen first, then placed into every
+    /// cell tapped until another digit is chosen.
+    pen_mode: bool,
+    /// The digit chosen in pen mode.
+    active_digit: Option<i8>,
+    /// Open solution explorer, a debugging view.
+    #[cfg(feature = "solution-explorer")]
+    explorer: Option<Explorer>,
     /// True if the selected cell currently conflicts with peers.
     collision: bool,
-    /// Difficulty slider value.
+    /// Peers holding the digit of the failed guess in `fail`, outlined so the
+    /// player sees the reason; cleared by the next edit or selection.
+    conflict_peers: Vec<usize>,
+    /// Difficulty slider value; only applies to the next new game.
     difficulty: f64,
+    /// Difficulty level the current puzzle was generated at.
+    level: u8,
+    /// Date (yyyymmdd) of the current puzzle if it is a puzzle of the day.
+    daily: Option<u32>,
+    /// Rules of the current puzzle.
+    variant: Variant,
+    /// Technique-based rating of the current puzzle.
+    rating: sudoku::Difficulty,
     /// Number of remaining empty cells.
     voids: usize,
-    /// Time when the current game started.
-    start_time: Instant,
+    /// Set by the first selection or guess; until then the clock stands still,
+    /// so reading the board doesn't count as playing time.
+    started: bool,
+    /// Playing time banked at the last timer tick or pause.
+    active_time: Duration,
+    /// When `active_time` was last brought up to date.
+    last_tick: Instant,
+    /// Cleared by the timer task once it can no longer reach the app; shared with that task.
+    timer_live: Arc<AtomicBool>,
     /// Frozen elapsed time (in seconds) once solved, otherwise `None`.
     stopped_time: Option<u64>,
+    /// Whether the clock counts up, or down towards a lost game.
+    timer_mode: Timer,
+    /// Number of hints taken.
+    hints_used: u32,
+    /// Number of board checks taken.
+    checks_used: u32,
+    /// Guesses found wrong by the last check; cleared by the next edit.
+    wrong: Vec<bool>,
+    /// Explanation of the next logical step; cleared by the next edit.
+    explanation: Option<Explanation>,
+    /// Most recent hint cell and when it was given, for a short highlight.
+    hint: Option<(usize, Instant)>,
+    /// Cell of the last click and when, to tell double clicks apart.
+    last_click: Option<(usize, Instant)>,
+    /// Cell a pointer went down on and when, to tell long presses from taps.
+    pressed: Option<(usize, Instant)>,
+    /// Cell of the last placement, whether the digit was correct, and when.
+    flash: Option<(usize, bool, Instant)>,
+    /// Cells of each unit recently filled with its solution, and when.
+    completed: Vec<(Vec<usize>, Instant)>,
+    /// True once the player got help; such games don't count toward statistics.
+    assisted: bool,
+    /// Set when "Solve" filled in the board: the game was given up, not won.
+    gave_up: bool,
+    /// Edits that can be undone, most recent last.
+    undo_stack: Vec<Edit>,
+    /// Undone edits that can be redone, most recent last.
+    redo_stack: Vec<Edit>,
+    /// Position saved with "Bookmark"; a new game drops it.
+    bookmark: Option<Bookmark>,
+    /// Contents of the puzzle import field.
+    import_text: String,
+    /// Why the last import was rejected; a new game clears it.
+    import_error: Option<String>,
+    /// Why no new puzzle could be generated; a new game clears it.
+    generation_error: Option<String>,
+    /// Inputs of the worksheet generator.
+    worksheet: Worksheet,
+    /// Set when the game changed since it was last saved.
+    unsaved: bool,
+    /// Where the game is saved; `None` keeps it in memory only.
+    save_path: Option<PathBuf>,
+    /// Statistics across sessions.
+    stats: Stats,
+    /// Where `stats` is persisted; `None` keeps them in memory only.
+    stats_path: Option<PathBuf>,
+    /// Player preferences, including those for new games.
+    settings: Settings,
+    /// Where `settings` are persisted; `None` keeps them in memory only.
+    settings_path: Option<PathBuf>,
+    /// True if the finished game beat the stored best time.
+    new_record: bool,
+    /// Size of the play area, board and number pad, as last laid out.
+    play_space: Size,
+    /// When the window was last resized, while that size isn't written yet.
+    window_resized: Option<Instant>,
 }
 
 impl AppState {
     fn new(difficulty: f64) -> Self {
+        Self::with_settings(difficulty, Settings::default())
+    }
+
+    /// A first game on the classic board, generated as `settings` ask for.
+    fn with_settings(difficulty: f64, settings: Settings) -> Self {
+        let level = difficulty_level(difficulty);
+        let sudoku = generate_puzzle(level, DEFAULT_BLOCK_SIDE, &settings)
+            .or_else(|| sudoku::Sudoku::new(level, DEFAULT_BLOCK_SIDE, Variant::Classic));
+        let Some(sudoku) = sudoku else {
+            // Start on an empty board rather than not at all; "New Game" tries again.
+            let cells = Geometry {
+                block_side: DEFAULT_BLOCK_SIDE,
+            }
+            .cell_count();
+            let empty = sudoku::Sudoku(vec![0; cells], vec![0; cells], Variant::Classic);
+            let mut state = Self::from_sudoku(empty, difficulty);
+            state.settings = settings;
+            state.generation_error = Some("Could not generate a puzzle".into());
+            return state;
+        };
+        let mut state = Self::from_sudoku(sudoku, difficulty);
+        state.settings = settings;
+        state
+    }
+
+    fn from_sudoku(sudoku: sudoku::Sudoku, difficulty: f64) -> Self {
+        let level = difficulty_level(difficulty);
+        let rating = sudoku.rate();
+        let geometry = Geometry {
+            block_side: sudoku.block_side(),
+        };
+        let cells = geometry.cell_count();
         // Properly destructure the tuple struct `Sudoku`
-        let sudoku::Sudoku(puzzle, solution) = sudoku::Sudoku::new(difficulty as u8);
+        let sudoku::Sudoku(puzzle, solution, variant) = sudoku;
 
         let voids = puzzle.iter().filter(|&&n| n == 0).count();
 
         Self {
             active: true,
+            geometry,
+            is_clue: puzzle.iter().map(|&v| v != 0).collect(),
             sudoku: puzzle,
             solved: solution,
-            is_clue: puzzle.map(|v| v != 0),
-            highlight: [false; CELL_COUNT],
+            revealed: vec![false; cells],
+            notes: vec![[false; MAX_SIDE]; cells],
+            center_notes: vec![[false; MAX_SIDE]; cells],
+            note_mode: NoteMode::Off,
+            note_selection: false,
+            highlight: vec![false; cells],
+            highlight_same: vec![false; cells],
+            highlight_fits: vec![false; cells],
             selected_cell: None,
             fail: None,
             fails: 0,
+            guesses: 0,
+            correct_guesses: 0,
+            rejected: None,
+            game_over: false,
+            time_up: false,
+            solved_dialog_open: false,
+            confirm_new_game: false,
+            confirm_reveal: None,
+            daily_requested: false,
+            preset_requested: None,
+            help_open: false,
+            history_open: false,
+            picker: None,
+            dragged: None,
+            pen_mode: false,
+            active_digit: None,
+            #[cfg(feature = "solution-explorer")]
+            explorer: None,
             collision: false,
-            difficulty,
+            conflict_peers: Vec::new(),
+            // The level actually used, so the slider shows it.
+            difficulty: level as f64,
+            level,
+            daily: None,
+            variant,
+            rating,
             voids,
-            start_time: Instant::now(),
+            started: false,
+            active_time: Duration::ZERO,
+            last_tick: Instant::now(),
+            timer_live: Arc::new(AtomicBool::new(true)),
             stopped_time: None,
+            timer_mode: Timer::CountUp,
+            hints_used: 0,
+            checks_used: 0,
+            wrong: vec![false; cells],
+            explanation: None,
+            hint: None,
+            last_click: None,
+            pressed: None,
+            flash: None,
+            completed: Vec::new(),
+            assisted: false,
+            gave_up: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            bookmark: None,
+            import_text: String::new(),
+            import_error: None,
+            generation_error: None,
+            worksheet: Worksheet::default(),
+            unsaved: true,
+            stats: Stats::default(),
+            stats_path: None,
+            save_path: None,
+            settings: Settings::default(),
+            settings_path: None,
+            new_record: false,
+            play_space: Size::ZERO,
+            window_resized: None,
         }
     }
 
-    fn new_game(&mut self) {
-        *self = Self::new(self.difficulty);
+    /// Replace the board with `sudoku`, keeping the player's settings.
+    fn start_game(&mut self, sudoku: sudoku::Sudoku) {
+        let mut next = Self::from_sudoku(sudoku, self.difficulty);
+        next.note_mode = self.note_mode;
+        next.pen_mode = self.pen_mode;
+        next.timer_mode = self.timer_mode;
+        next.stats = std::mem::take(&mut self.stats);
+        next.stats_path = self.stats_path.take();
+        next.save_path = self.save_path.take();
+        // The running timer task keeps reporting through the same flag.
+        next.timer_live = self.timer_live.clone();
+        next.settings = std::mem::take(&mut self.settings);
+        next.settings_path = self.settings_path.take();
+        next.worksheet = std::mem::take(&mut self.worksheet);
+        // The layout only reports changes, so the next board must know the room it has.
+        next.play_space = self.play_space;
+        next.window_resized = self.window_resized;
+        *self = next;
+        self.reset_interaction_state();
+        self.save_game();
     }
 
-    fn elapsed_seconds(&self) -> u64 {
-        self.stopped_time
-            .unwrap_or_else(|| self.start_time.elapsed().as_secs())
+    /// Forget everything that points into the board: the selection and its
+    /// highlights, the last failure, an open picker and fading effects. Called
+    /// whenever the board is replaced, as its cells may now be clues or gone.
+    fn reset_interaction_state(&mut self) {
+        let cells = self.geometry.cell_count();
+        self.selected_cell = None;
+        self.note_selection = false;
+        self.fail = None;
+        self.collision = false;
+        self.conflict_peers.clear();
+        self.rejected = None;
+        self.picker = None;
+        self.hint = None;
+        self.last_click = None;
+        self.pressed = None;
+        self.confirm_reveal = None;
+        self.flash = None;
+        self.completed.clear();
+        self.highlight = vec![false; cells];
+        self.highlight_same = vec![false; cells];
+        self.highlight_fits = vec![false; cells];
     }
 
-    fn recompute_voids_and_maybe_stop_timer(&mut self) {
-        self.voids = self.sudoku.iter().filter(|&&n| n == 0).count();
-        if self.voids == 0 && self.stopped_time.is_none() {
-            self.stopped_time = Some(self.start_time.elapsed().as_secs());
-        }
+    fn new_game(&mut self) {
+        self.new_game_on(self.geometry.block_side);
     }
 
-    fn clear_last_fail(&mut self) {
-        if let Some(idx) = self.fail.take() {
-            self.sudoku[idx] = 0;
+    /// Start a fresh puzzle on a board of `block_side`×`block_side` blocks. If
+    /// none can be generated, the current game stays and `generation_error` says so.
+    fn new_game_on(&mut self, block_side: usize) {
+        let level = difficulty_level(self.difficulty);
+        match generate_puzzle(level, block_side, &self.settings) {
+            Some(sudoku) => self.start_game(sudoku),
+            None => {
+                self.generation_error =
+                    Some("Could not generate a puzzle with these options".into())
+            }
         }
     }
 
-    /// Check if the value in `index` conflicts with same values in its row/col/block.
-    fn has_conflict(&self, index: usize) -> bool {
-        let value = self.sudoku[index];
-        if value == 0 {
-            return false;
-        }
+    /// A fresh puzzle following the current difficulty, board size and options.
+    fn generate_puzzle(&self) -> Option<sudoku::Sudoku> {
+        generate_puzzle(
+            difficulty_level(self.difficulty),
+            self.geometry.block_side,
+            &self.settings,
+        )
+    }
 
-        // Row
-        let start = row_start(index);
-        for offset in 0..SIDE {
-            let i = start + offset;
-            if i != index && self.sudoku[i] == value {
-                return true;
+    /// Generate the requested number of puzzles and write them to the
+    /// worksheet file; the outcome is reported in `worksheet.message`.
+    fn generate_worksheet(&mut self) {
+        let count = match self.worksheet.count.trim().parse() {
+            Ok(n) if (1..=MAX_WORKSHEET_PUZZLES).contains(&n) => n,
+            _ => {
+                self.worksheet.message =
+                    Some(format!("Enter a count from 1 to {MAX_WORKSHEET_PUZZLES}"));
+                return;
             }
+        };
+        let path = PathBuf::from(self.worksheet.path.trim());
+        if path.as_os_str().is_empty() {
+            self.worksheet.message = Some("Enter a file name".to_string());
+            return;
         }
 
-        // Column
-        let col = col_of(index);
-        for row in 0..SIDE {
-            let i = col + row * SIDE;
-            if i != index && self.sudoku[i] == value {
-                return true;
-            }
+        let Some(puzzles) = (0..count)
+            .map(|_| self.generate_puzzle())
+            .collect::<Option<Vec<_>>>()
+        else {
+            self.worksheet.message = Some("Could not generate the puzzles".to_string());
+            return;
+        };
+        let text = worksheet_text(&puzzles, self.worksheet.solutions);
+        self.worksheet.message = Some(match fs::write(&path, text) {
+            Ok(()) => format!("Wrote {count} puzzles to {}", path.display()),
+            Err(err) => format!("Could not write {}: {err}", path.display()),
+        });
+    }
+
+    /// The board as the player sees it, clues and entries alike, for export.
+    fn board_snapshot(&self) -> sudoku::Sudoku {
+        sudoku::Sudoku(self.sudoku.clone(), self.solved.clone(), self.variant)
+    }
+
+    /// Replay the current puzzle from its clues, with a fresh clock and counters.
+    fn restart(&mut self) {
+        let clues = (0..self.geometry.cell_count())
+            .map(|i| if self.is_clue[i] { self.solved[i] } else { 0 })
+            .collect();
+        let solution = std::mem::take(&mut self.solved);
+        // The same puzzle, so the same level, even if the slider moved since.
+        let (level, daily) = (self.level, self.daily);
+        self.start_game(sudoku::Sudoku(clues, solution, self.variant));
+        self.level = level;
+        self.daily = daily;
+    }
+
+    /// Start a new game, but ask first if that would discard progress.
+    fn request_new_game(&mut self) {
+        if self.has_progress() {
+            self.confirm_new_game = true;
+        } else {
+            self.new_game();
         }
+    }
 
-        // Block
-        let origin = block_origin(index);
-        for br in 0..BLOCK_SIDE {
-            for bc in 0..BLOCK_SIDE {
-                let i = origin + bc + br * SIDE;
-                if i != index && self.sudoku[i] == value {
-                    return true;
-                }
+    /// Start today's puzzle of the day, asking first as "New Game" does.
+    fn request_daily(&mut self) {
+        if self.has_progress() {
+            self.confirm_new_game = true;
+            self.daily_requested = true;
+        } else {
+            self.start_daily(today());
+        }
+    }
+
+    /// Go ahead with the game asked for at the "Discard current game?" prompt.
+    fn accept_new_game(&mut self) {
+        if self.daily_requested {
+            self.start_daily(today());
+        } else {
+            if let Some(level) = self.preset_requested {
+                self.difficulty = level as f64;
             }
+            self.new_game();
         }
+    }
 
-        false
+    /// Keep the current game, and the difficulty, at the "Discard current
+    /// game?" prompt.
+    fn decline_new_game(&mut self) {
+        self.confirm_new_game = false;
+        self.daily_requested = false;
+        self.preset_requested = None;
     }
 
-    /// Apply a user guess to `index`.
-    fn apply_guess(&mut self, index: usize, digit: i8) {
-        if self.is_clue[index] {
+    /// Start the puzzle of the day `date`: generated from the date alone, so
+    /// it is the same board for everyone, whatever the player's options.
+    fn start_daily(&mut self, date: u32) {
+        let Some(sudoku) = sudoku::Sudoku::new_seeded(DAILY_LEVEL, u64::from(date)) else {
+            self.generation_error = Some("Could not generate the puzzle of the day".into());
             return;
-        }
+        };
+        self.start_game(sudoku);
+        self.level = DAILY_LEVEL;
+        self.daily = Some(date);
+    }
 
-        self.sudoku[index] = digit;
-        self.recompute_voids_and_maybe_stop_timer();
+    /// Whether the player filled some cells of a game that is still open.
+    fn has_progress(&self) -> bool {
+        self.stopped_time.is_none()
+            && !self.game_over
+            && (0..self.geometry.cell_count()).any(|i| !self.is_clue[i] && self.sudoku[i] != 0)
+    }
 
-        self.fail = None;
-        self.collision = false;
+    /// Start a game at the difficulty level `level`, asking first as "New
+    /// Game" does; the difficulty only changes once the game starts.
+    fn start_preset(&mut self, level: u8) {
+        if self.has_progress() {
+            self.confirm_new_game = true;
+            self.preset_requested = Some(level);
+        } else {
+            self.difficulty = level as f64;
+            self.new_game();
+        }
+    }
 
-        // Only treat as a failure if it's not the correct solution and it conflicts.
-        if self.sudoku[index] != self.solved[index] && self.has_conflict(index) {
-            self.collision = true;
-            self.fails += 1;
-            self.fail = Some(index);
+    /// Switch to a board of `block_side`×`block_side` blocks with a fresh puzzle.
+    fn set_board_size(&mut self, block_side: usize) {
+        self.new_game_on(block_side);
+    }
+
+    /// Load the puzzle line typed into the import field. An unusable line
+    /// leaves the current game untouched and sets `import_error`.
+    fn import(&mut self) {
+        match sudoku::Sudoku::from_str_line(&self.import_text) {
+            Ok(sudoku) => self.start_game(sudoku),
+            Err(err) => self.import_error = Some(format!("Import failed: {err}")),
         }
     }
 
-    fn clear_highlight(&mut self) {
-        self.highlight = [false; CELL_COUNT];
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let saved = SavedGame {
+            board: self.sudoku.clone(),
+            solution: self.solved.clone(),
+            is_clue: self.is_clue.clone(),
+            elapsed_seconds: self.elapsed_seconds(),
+            fails: self.fails,
+            difficulty: self.level as f64,
+            diagonal: self.variant == Variant::Diagonal,
+            guesses: self.guesses,
+            correct_guesses: self.correct_guesses,
+            daily: self.daily,
+            countdown: match self.timer_mode {
+                Timer::CountUp => None,
+                Timer::CountDown(limit) => Some(limit.as_secs()),
+            },
+            assisted: self.assisted,
+            hints_used: self.hints_used,
+            checks_used: self.checks_used,
+            gave_up: self.gave_up,
+            revealed: self.revealed.clone(),
+            notes: self.notes.clone(),
+            center_notes: self.center_notes.clone(),
+            game_over: self.game_over,
+            time_up: self.time_up,
+        };
+        write_json(path, &saved)
     }
 
-    fn highlight_row_col_block(&mut self, index: usize) {
-        self.clear_highlight();
+    fn load(path: &Path) -> io::Result<Self> {
+        let saved: SavedGame =
+            serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::other)?;
 
-        // Row
-        let start = row_start(index);
-        for offset in 0..SIDE {
-            self.highlight[start + offset] = true;
+        let SavedGame {
+            board,
+            solution,
+            is_clue,
+            revealed,
+            notes,
+            center_notes,
+            ..
+        } = saved;
+        // The board size follows from the number of cells.
+        let cells = board.len();
+        let Some(side) = BOARD_SIZES
+            .iter()
+            .find(|b| b.pow(4) == cells)
+            .map(|b| (b * b) as i8)
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed saved game",
+            ));
+        };
+        // Digits outside the board would index past its grids later on.
+        let digits_in = |values: &[i8], low: i8| values.iter().all(|v| (low..=side).contains(v));
+        // Older saves have no per-cell grids beyond the board.
+        let fits = |len: usize| len == 0 || len == cells;
+        if solution.len() != cells
+            || is_clue.len() != cells
+            || !digits_in(&board, 0)
+            || !digits_in(&solution, 1)
+            || !fits(revealed.len())
+            || !fits(notes.len())
+            || !fits(center_notes.len())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed saved game",
+            ));
         }
 
-        // Column
-        let col = col_of(index);
-        for row in 0..SIDE {
-            self.highlight[col + row * SIDE] = true;
+        let clues = (0..cells)
+            .map(|i| if is_clue[i] { board[i] } else { 0 })
+            .collect();
+        let variant = if saved.diagonal {
+            Variant::Diagonal
+        } else {
+            Variant::Classic
+        };
+        let mut state = Self::from_sudoku(
+            sudoku::Sudoku(clues, solution, variant),
+            saved.difficulty,
+        );
+        state.sudoku = board;
+        if !revealed.is_empty() {
+            state.revealed = revealed;
         }
+        if !notes.is_empty() {
+            state.notes = notes;
+        }
+        if !center_notes.is_empty() {
+            state.center_notes = center_notes;
+        }
+        state.reset_interaction_state();
+        state.fails = saved.fails;
+        state.assisted = saved.assisted;
+        state.hints_used = saved.hints_used;
+        state.checks_used = saved.checks_used;
+        state.gave_up = saved.gave_up;
+        state.guesses = saved.guesses;
+        state.correct_guesses = saved.correct_guesses;
+        state.daily = saved.daily;
+        state.timer_mode = saved.countdown.map_or(Timer::CountUp, |limit| {
+            Timer::CountDown(Duration::from_secs(limit))
+        });
+        // Continue the clock from the saved value.
+        state.active_time = Duration::from_secs(saved.elapsed_seconds);
+        // A game saved after it was solved or lost stays stopped and isn't counted again.
+        state.game_over = saved.game_over;
+        state.time_up = saved.time_up;
+        if !state.sudoku.contains(&0) || state.game_over {
+            state.stopped_time = Some(saved.elapsed_seconds);
+        }
+        state.recompute_voids_and_maybe_stop_timer();
+        Ok(state)
+    }
 
-        // Block
-        let origin = block_origin(index);
-        for br in 0..BLOCK_SIDE {
-            for bc in 0..BLOCK_SIDE {
-                self.highlight[origin + bc + br * SIDE] = true;
-            }
+    /// Write the game to `path` if it changed since the last save. The clock
+    /// alone doesn't count, so the saved time is the one of the last edit or pause.
+    fn autosave(&mut self, path: &Path) {
+        if self.unsaved && self.save(path).is_ok() {
+            self.unsaved = false;
         }
     }
 
-    fn select_cell(&mut self, index: usize) {
-        self.clear_last_fail();
+    /// Write the game to `save_path`, if there is one, when it changed.
+    fn save_game(&mut self) {
+        if let Some(path) = self.save_path.clone() {
+            self.autosave(&path);
+        }
+    }
 
-        if !self.is_clue[index] {
-            self.selected_cell = Some(index);
+    /// Playing time since the first move, excluding paused intervals.
+    ///
+    /// The clock only advances by at most `MAX_TICK_DELTA` between ticks, so
+    /// the time the machine spends suspended isn't counted.
+    fn active_duration(&self) -> Duration {
+        if self.active && self.started {
+            self.active_time + self.last_tick.elapsed().min(MAX_TICK_DELTA)
+        } else {
+            self.active_time
         }
+    }
 
-        self.highlight_row_col_block(index);
+    /// Start the clock on the first interaction with the board.
+    fn start_clock(&mut self) {
+        if !self.started {
+            self.started = true;
+            self.last_tick = Instant::now();
+        }
     }
-}
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::new(DEFAULT_DIFFICULTY)
+    /// Bank the playing time since the last tick; called by the timer task.
+    fn tick(&mut self) {
+        self.active_time = self.active_duration();
+        self.last_tick = Instant::now();
     }
-}
 
-// --- Views ------------------------------------------------------------------------------------
+    fn elapsed_seconds(&self) -> u64 {
+        self.stopped_time
+            .unwrap_or_else(|| self.active_duration().as_secs())
+    }
 
-fn number_grid() -> impl WidgetView<AppState> + use<> {
-    // Digit buttons 1–9 (explicit loop instead of iterator `.map()` to avoid ICE)
-    let mut number_cells = Vec::new();
-    for i in 0..9 {
-        let digit = i + 1;
-        let btn = text_button(format!("{digit}"), move |state: &mut AppState| {
-            if let Some(index) = state.selected_cell {
-                state.apply_guess(index, digit as i8);
-            }
-        })
-        .padding(0.0)
-        .background_color(SOURCE_BG)
-        .corner_radius(0.0)
-        .border_color(Color::TRANSPARENT)
-        .grid_pos(i, 0);
-        number_cells.push(btn);
+    /// The time the clock shows: played so far, or left in a countdown.
+    fn clock_seconds(&self) -> u64 {
+        match self.timer_mode {
+            Timer::CountUp => self.elapsed_seconds(),
+            Timer::CountDown(limit) => limit.as_secs().saturating_sub(self.elapsed_seconds()),
+        }
     }
 
-    grid(number_cells, 9, 1).gap(GRID_GAP)
-}
+    /// End a countdown game whose time ran out; called by the timer task.
+    fn check_countdown(&mut self) {
+        let Timer::CountDown(limit) = self.timer_mode else {
+            return;
+        };
+        if self.stopped_time.is_none() && !self.game_over && self.active_duration() >= limit {
+            self.play(Effect::Fail);
+            self.game_over = true;
+            self.time_up = true;
+            self.stopped_time = Some(limit.as_secs());
+        }
+    }
 
-fn cell(state: &mut AppState, index: usize) -> impl WidgetView<AppState> + use<> {
-    let value = state.sudoku[index];
+    /// Switch to the next timer preset. Only offered between games, so a
+    /// running clock can't be turned into a countdown it already lost.
+    fn cycle_timer_mode(&mut self) {
+        let current = TIMER_PRESETS
+            .iter()
+            .position(|&mode| mode == self.timer_mode)
+            .unwrap_or(0);
+        self.timer_mode = TIMER_PRESETS[(current + 1) % TIMER_PRESETS.len()];
+    }
+
+    /// Strength of the placement flash on `index`, fading from 1 to 0, and
+    /// whether the placed digit was correct.
+    fn flash_level(&self, index: usize) -> Option<(bool, f32)> {
+        let (i, correct, at) = self.flash?;
+        let left = 1.0 - at.elapsed().as_secs_f32() * 1000.0 / FLASH_MS as f32;
+        (i == index && left > 0.0).then_some((correct, left))
+    }
+
+    /// Drop a flash that has faded out, so the board settles to its normal colors.
+    fn expire_flash(&mut self) {
+        if self
+            .flash
+            .is_some_and(|(_, _, at)| at.elapsed() >= Duration::from_millis(FLASH_MS))
+        {
+            self.flash = None;
+        }
+    }
+
+    /// Strength of the completion glow on `index`, fading from 1 to 0; the
+    /// most recent unit through it counts.
+    fn completion_level(&self, index: usize) -> Option<f32> {
+        self.completed
+            .iter()
+            .filter(|(cells, _)| cells.contains(&index))
+            .map(|(_, at)| 1.0 - at.elapsed().as_secs_f32() * 1000.0 / COMPLETION_MS as f32)
+            .reduce(f32::max)
+            .filter(|&left| left > 0.0)
+    }
+
+    /// Drop the completion glows that have faded out.
+    fn expire_completed(&mut self) {
+        self.completed
+            .retain(|(_, at)| at.elapsed() < Duration::from_millis(COMPLETION_MS));
+    }
+
+    /// Start a completion glow for each unit through `index` that now holds
+    /// its solution in full.
+    fn mark_completed_units(&mut self, index: usize) {
+        let now = Instant::now();
+        let diagonal = self.variant == Variant::Diagonal;
+        for cells in sudoku::units_through(self.geometry.block_side, index, diagonal) {
+            if cells.iter().all(|&i| self.sudoku[i] == self.solved[i]) {
+                self.completed.push((cells, now));
+            }
+        }
+    }
+
+    /// Time until the view next needs refreshing: when the timer label reaches
+    /// the next whole second, or earlier if a hint highlight expires first.
+    fn until_next_tick(&self) -> Duration {
+        let second = Duration::from_secs(1);
+        let mut delay = if self.stopped_time.is_some() {
+            second
+        } else {
+            second - Duration::from_nanos(self.active_duration().subsec_nanos().into())
+        };
+        if let Some((_, at)) = self.hint {
+            let left = Duration::from_millis(HINT_HIGHLIGHT_MS).saturating_sub(at.elapsed());
+            if !left.is_zero() {
+                delay = delay.min(left);
+            }
+        }
+        delay
+    }
+
+    /// Share of placed digits that were correct, in percent; `None` before the
+    /// first move, and in freeform mode, where it would give mistakes away.
+    fn accuracy(&self) -> Option<f64> {
+        (self.guesses > 0 && !self.settings.freeform)
+            .then(|| 100.0 * self.correct_guesses as f64 / self.guesses as f64)
+    }
+
+    /// Whether cells are being filled faster than in the best recorded game at
+    /// this level, whose pace is taken as this puzzle's empty cells in the best
+    /// time. `None` without a best time to compare with, before the first move
+    /// and once the game is over.
+    fn ahead_of_best_pace(&self) -> Option<bool> {
+        if !self.started
+            || self.stopped_time.is_some()
+            || self.geometry.block_side != DEFAULT_BLOCK_SIDE
+        {
+            return None;
+        }
+        let best = self.stats.best_time(self.level)?;
+        let to_fill = self.is_clue.iter().filter(|&&c| !c).count() as u64;
+        let filled = to_fill - self.voids as u64;
+        // filled / elapsed >= to_fill / best, without dividing by zero.
+        Some(filled * best >= self.elapsed_seconds() * to_fill)
+    }
+
+    /// Whether the board accepts input: not paused and not lost.
+    fn playable(&self) -> bool {
+        self.active && !self.game_over
+    }
+
+    fn set_font_scale(&mut self, scale: f64) {
+        self.settings.font_scale = scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+        self.save_settings();
+    }
+
+    fn set_board_zoom(&mut self, zoom: f64) {
+        self.settings.board_zoom = zoom.clamp(MIN_BOARD_ZOOM, MAX_BOARD_ZOOM);
+        self.save_settings();
+    }
+
+    /// How much the board is magnified; only boards larger than the classic
+    /// one, whose cells get small, are zoomed.
+    fn board_zoom(&self) -> f64 {
+        if self.geometry.block_side > DEFAULT_BLOCK_SIDE {
+            self.settings.board_zoom
+        } else {
+            MIN_BOARD_ZOOM
+        }
+    }
+
+    /// Switch to the next set of glyphs for the digits.
+    fn cycle_glyphs(&mut self) {
+        let sets = GlyphSet::ALL;
+        let current = sets
+            .iter()
+            .position(|&set| set == self.settings.glyphs)
+            .unwrap_or(0);
+        self.settings.glyphs = sets[(current + 1) % sets.len()];
+        self.save_settings();
+    }
+
+    /// Move the number pad to the next side of the board.
+    fn cycle_pad_position(&mut self) {
+        let positions = PadPosition::ALL;
+        let current = positions
+            .iter()
+            .position(|&pos| pos == self.settings.pad_position)
+            .unwrap_or(0);
+        self.settings.pad_position = positions[(current + 1) % positions.len()];
+        self.save_settings();
+    }
+
+    /// Remember the window's new `size` for the next start, in whole pixels.
+    /// It is written once the window has kept it for `WINDOW_SAVE_DELAY`, so a
+    /// drag doesn't write the settings for every step.
+    fn record_window_size(&mut self, size: Size) {
+        let size = (size.width.round(), size.height.round());
+        if self.settings.window_size != Some(size) {
+            self.settings.window_size = Some(size);
+            self.window_resized = Some(Instant::now());
+        }
+    }
+
+    /// Write a recorded window size once resizing has settled.
+    fn save_window_size_if_settled(&mut self) {
+        if self
+            .window_resized
+            .is_some_and(|at| at.elapsed() >= WINDOW_SAVE_DELAY)
+        {
+            self.window_resized = None;
+            self.save_settings();
+        }
+    }
+
+    /// Play `effect` unless the player muted sound.
+    fn play(&self, effect: Effect) {
+        if self.settings.sound {
+            sound::play(effect);
+        }
+    }
+
+    fn save_settings(&self) {
+        if let Some(path) = &self.settings_path {
+            // Like statistics, settings are a nicety; a failed write is ignored.
+            let _ = self.settings.save(path);
+        }
+    }
+
+    /// Switch to the next clue count preset for new puzzles.
+    fn cycle_clue_target(&mut self) {
+        let current = CLUE_TARGET_PRESETS
+            .iter()
+            .position(|&clues| clues == self.settings.clue_target)
+            .unwrap_or(0);
+        self.settings.clue_target = CLUE_TARGET_PRESETS[(current + 1) % CLUE_TARGET_PRESETS.len()];
+        self.save_settings();
+    }
+
+    /// Switch to the next mistake limit preset.
+    fn cycle_fail_limit(&mut self) {
+        let current = FAIL_LIMIT_PRESETS
+            .iter()
+            .position(|&limit| limit == self.settings.max_fails)
+            .unwrap_or(0);
+        self.settings.max_fails = FAIL_LIMIT_PRESETS[(current + 1) % FAIL_LIMIT_PRESETS.len()];
+        self.save_settings();
+    }
+
+    /// Switch to the next highlight mode and apply it to the current selection.
+    fn cycle_highlight_mode(&mut self) {
+        let modes = HighlightMode::ALL;
+        let current = modes
+            .iter()
+            .position(|&mode| mode == self.settings.highlight_mode)
+            .unwrap_or(0);
+        self.settings.highlight_mode = modes[(current + 1) % modes.len()];
+        self.save_settings();
+        match self.selected_cell {
+            Some(index) => self.highlight_row_col_block(index),
+            None => self.clear_highlight(),
+        }
+        self.refresh_same_digit();
+    }
+
+    fn toggle_pause(&mut self) {
+        if !self.active {
+            self.last_tick = Instant::now();
+            self.active = true;
+        } else if self.stopped_time.is_none() {
+            self.tick();
+            self.active = false;
+            self.unsaved = true;
+            // The timer task, which saves otherwise, ends with the pause.
+            self.save_game();
+        }
+    }
+
+    /// Give up and fill in the whole solution.
+    fn reveal_solution(&mut self) {
+        for index in 0..self.geometry.cell_count() {
+            if self.sudoku[index] != self.solved[index] {
+                self.revealed[index] = true;
+            }
+        }
+        self.sudoku = self.solved.clone();
+        self.notes.fill([false; MAX_SIDE]);
+        self.center_notes.fill([false; MAX_SIDE]);
+        self.fail = None;
+        self.collision = false;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.assisted = true;
+        self.gave_up = true;
+        self.recompute_voids_and_maybe_stop_timer();
+        self.refresh_same_digit();
+    }
+
+    /// Remember the board and notes, replacing an earlier bookmark.
+    fn bookmark(&mut self) {
+        self.bookmark = Some(Bookmark {
+            board: self.sudoku.clone(),
+            notes: self.notes.clone(),
+            center_notes: self.center_notes.clone(),
+            revealed: self.revealed.clone(),
+        });
+    }
+
+    /// Go back to the bookmarked board and notes; the bookmark stays for
+    /// another try. Undo history belongs to the abandoned line and is dropped,
+    /// while moves and fails made on it still count.
+    fn restore_bookmark(&mut self) {
+        let Some(mark) = self.bookmark.clone() else {
+            return;
+        };
+        self.sudoku = mark.board;
+        self.notes = mark.notes;
+        self.center_notes = mark.center_notes;
+        self.revealed = mark.revealed;
+        self.fail = None;
+        self.collision = false;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.recompute_voids_and_maybe_stop_timer();
+        if let Some(index) = self.selected_cell {
+            self.highlight_row_col_block(index);
+        }
+        self.refresh_same_digit();
+    }
+
+    /// Called after every board change.
+    fn recompute_voids_and_maybe_stop_timer(&mut self) {
+        self.unsaved = true;
+        self.conflict_peers.clear();
+        self.wrong.fill(false);
+        self.explanation = None;
+        self.rejected = None;
+        self.voids = self.sudoku.iter().filter(|&&n| n == 0).count();
+        if self.is_solved() && self.stopped_time.is_none() {
+            let seconds = self.active_duration().as_secs();
+            self.stopped_time = Some(seconds);
+            self.record_completion(seconds);
+            // Giving up with "Solve" is nothing to celebrate; finishing after a hint is.
+            self.solved_dialog_open = !self.gave_up;
+            if self.solved_dialog_open {
+                self.play(Effect::Win);
+            }
+        }
+    }
+
+    /// Whether every cell holds its solution digit. A full board isn't enough:
+    /// a wrong digit that conflicts with nothing stays on the board.
+    ///
+    /// The board must also follow the rules. A generated solution always
+    /// does, but one restored from a damaged save file might not.
+    fn is_solved(&self) -> bool {
+        self.voids == 0
+            && self.sudoku == self.solved
+            && (0..self.sudoku.len()).all(|i| !self.has_conflict(i))
+    }
+
+    fn record_completion(&mut self, seconds: u64) {
+        // Difficulty levels and best times refer to the classic board; other sizes don't compare.
+        if self.geometry.block_side != DEFAULT_BLOCK_SIDE {
+            return;
+        }
+        let game = Completion {
+            seconds,
+            fails: self.fails,
+            assisted: self.assisted,
+            score: self.score(),
+        };
+        self.new_record = self.stats.record(self.level, game);
+        if let Some(date) = self.daily {
+            self.stats.record_daily(date, game);
+        }
+        if let Some(path) = &self.stats_path {
+            // Statistics are a nicety; failing to write them must not disturb the game.
+            let _ = self.stats.save(path);
+        }
+    }
+
+    /// Points for the current game, from the `SCORE_*` constants. Giving up
+    /// with "Solve" scores nothing.
+    fn score(&self) -> i64 {
+        if self.gave_up {
+            return 0;
+        }
+        let base = SCORE_BASE + SCORE_PER_LEVEL * self.level as i64;
+        let penalty = SCORE_PER_SECOND * self.elapsed_seconds() as i64
+            + SCORE_PER_FAIL * self.fails as i64
+            + SCORE_PER_HINT * self.hints_used as i64
+            + SCORE_PER_CHECK * self.checks_used as i64;
+        (base - penalty).max(0)
+    }
+
+    /// Erase the failed guess, if any. `collision` describes that guess, so it
+    /// goes too; left set, it would paint the next selected guess red.
+    fn clear_last_fail(&mut self) {
+        if let Some(idx) = self.fail.take() {
+            self.record_edit(idx, self.sudoku[idx], 0, Vec::new());
+            self.sudoku[idx] = 0;
+            self.collision = false;
+            self.recompute_voids_and_maybe_stop_timer();
+        }
+    }
+
+    /// Push an edit onto the undo stack; any new edit invalidates the redo stack.
+    fn record_edit(&mut self, index: usize, old: i8, new: i8, struck: Vec<usize>) {
+        if old != new || !struck.is_empty() {
+            self.undo_stack.push(Edit {
+                index,
+                old,
+                new,
+                struck,
+                notes: self.notes[index],
+                center_notes: self.center_notes[index],
+            });
+            self.redo_stack.clear();
+        }
+    }
+
+    /// Write `value` into `index` without recording history, then restore
+    /// the fail/collision flags so they match the restored board.
+    fn restore_cell(&mut self, index: usize, value: i8) {
+        self.sudoku[index] = value;
+        self.recompute_voids_and_maybe_stop_timer();
+
+        self.selected_cell = Some(index);
+        self.highlight_row_col_block(index);
+        self.refresh_same_digit();
+
+        self.fail = None;
+        self.collision = false;
+        if value != 0 && self.is_mistake(index) {
+            self.collision = true;
+            self.fail = Some(index);
+            self.conflict_peers = self.conflicting_peers(index);
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(edit) = self.undo_stack.pop() {
+            self.restore_cell(edit.index, edit.old);
+            self.set_note(&edit.struck, edit.new, true);
+            self.notes[edit.index] = edit.notes;
+            self.center_notes[edit.index] = edit.center_notes;
+            self.redo_stack.push(edit);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(edit) = self.redo_stack.pop() {
+            self.restore_cell(edit.index, edit.new);
+            self.set_note(&edit.struck, edit.new, false);
+            if edit.new != 0 {
+                self.notes[edit.index] = [false; MAX_SIDE];
+                self.center_notes[edit.index] = [false; MAX_SIDE];
+            }
+            self.undo_stack.push(edit);
+        }
+    }
+
+    /// Check if `value` could go into `index` without repeating a digit in its row/col/block.
+    fn can_place(&self, index: usize, value: i8) -> bool {
+        self.peer_digits(index) & digit_bit(value) == 0
+    }
+
+    /// Digits that can still go into `index`, bit `d - 1` standing for digit
+    /// `d`; 0 for filled cells. The one place candidates are worked out: notes,
+    /// the heatmap, hints and the pen highlight all build on it.
+    fn candidates(&self, index: usize) -> u16 {
+        if self.sudoku[index] != 0 {
+            return 0;
+        }
+        let all = ((1u32 << self.geometry.side()) - 1) as u16;
+        all & !self.peer_digits(index)
+    }
+
+    /// What cell `index` holds, as the mini-map shows it.
+    fn fill(&self, index: usize) -> Fill {
+        if self.is_clue[index] {
+            Fill::Clue
+        } else if self.sudoku[index] != 0 {
+            Fill::Guess
+        } else {
+            Fill::Empty
+        }
+    }
+
+    /// Number of digits that could still go into `index`; 0 for filled cells.
+    fn candidate_count(&self, index: usize) -> usize {
+        self.candidates(index).count_ones() as usize
+    }
+
+    /// Digits in the row, column and block of `index`, the cell itself
+    /// included, and on its diagonals in the diagonal variant; as in `candidates`.
+    fn peer_digits(&self, index: usize) -> u16 {
+        let g = self.geometry;
+        let (side, block_side) = (g.side(), g.block_side);
+        let start = g.row_start(index);
+        let col = g.col_of(index);
+        let origin = g.block_origin(index);
+
+        let mut used = (0..side).fold(0, |used, k| {
+            used | digit_bit(self.sudoku[start + k])
+                | digit_bit(self.sudoku[col + k * side])
+                | digit_bit(self.sudoku[origin + k % block_side + k / block_side * side])
+        });
+        if self.variant == Variant::Diagonal {
+            for i in sudoku::diagonal_cells(block_side, index) {
+                used |= digit_bit(self.sudoku[i]);
+            }
+        }
+        used
+    }
+
+    /// Fill one empty cell from the solution, preferring a naked single.
+    fn give_hint(&mut self) {
+        let empty: Vec<usize> = (0..self.geometry.cell_count())
+            .filter(|&i| !self.is_clue[i] && self.sudoku[i] == 0)
+            .collect();
+        let naked_singles: Vec<usize> = empty
+            .iter()
+            .copied()
+            .filter(|&i| self.candidate_count(i) == 1)
+            .collect();
+
+        let pool = if naked_singles.is_empty() {
+            &empty
+        } else {
+            &naked_singles
+        };
+        if let Some(&index) = pool.choose(&mut rng()) {
+            self.reveal_cell(index);
+        }
+    }
+
+    /// Reveal the cell a double click asked about, and close the prompt.
+    fn accept_reveal(&mut self) {
+        if let Some(index) = self.confirm_reveal.take() {
+            self.reveal_cell(index);
+        }
+    }
+
+    /// Fill the empty cell `index` from the solution as a hint.
+    fn reveal_cell(&mut self, index: usize) {
+        if self.is_clue[index] || self.sudoku[index] != 0 {
+            return;
+        }
+        self.apply_guess(index, self.solved[index]);
+        self.revealed[index] = true;
+        self.hints_used += 1;
+        self.assisted = true;
+        self.hint = Some((index, Instant::now()));
+    }
+
+    /// Explain the next digit logic places on the board as it stands, without
+    /// placing it, and highlight the cells the reasoning rests on. Counts as a hint.
+    fn explain(&mut self) {
+        let side = self.geometry.side();
+        self.explanation = Some(match self.board_snapshot().next_step() {
+            Some(step) => {
+                let reason = match step.unit {
+                    Some(unit) => format!("{} in {unit}", step.technique),
+                    None => step.technique.to_string(),
+                };
+                let after = match step.after {
+                    Some(technique) => format!(", after {technique}"),
+                    None => String::new(),
+                };
+                self.hints_used += 1;
+                self.assisted = true;
+                self.unsaved = true;
+                Explanation {
+                    text: format!(
+                        "R{}C{} must be {} ({reason}{after})",
+                        step.index / side + 1,
+                        step.index % side + 1,
+                        self.settings.glyphs.glyph(step.value),
+                    ),
+                    target: Some(step.index),
+                    cells: step.cells,
+                }
+            }
+            None => Explanation {
+                text: "No step found by the known techniques; try notes".to_string(),
+                target: None,
+                cells: Vec::new(),
+            },
+        });
+    }
+
+    /// Flag every guess that differs from the solution, even without a conflict.
+    ///
+    /// Each check costs `CHECK_PENALTY_SECS` on the clock, and the game no
+    /// longer counts toward best times and the streak.
+    fn check_board(&mut self) {
+        self.wrong = (0..self.geometry.cell_count())
+            .map(|i| !self.is_clue[i] && self.sudoku[i] != 0 && self.sudoku[i] != self.solved[i])
+            .collect();
+        self.checks_used += 1;
+        self.assisted = true;
+        self.active_time += Duration::from_secs(CHECK_PENALTY_SECS);
+        self.unsaved = true;
+    }
+
+    /// Check if the value in `index` conflicts with same values in its row/col/block,
+    /// and on its diagonals in the diagonal variant.
+    fn has_conflict(&self, index: usize) -> bool {
+        !self.conflicting_peers(index).is_empty()
+    }
+
+    /// The cells in the row, column and block of `index`, and on its diagonals
+    /// in the diagonal variant, that hold the same digit; in ascending order.
+    fn conflicting_peers(&self, index: usize) -> Vec<usize> {
+        let value = self.sudoku[index];
+        if value == 0 {
+            return Vec::new();
+        }
+        let g = self.geometry;
+        let side = g.side();
+
+        let row = (0..side).map(|offset| g.row_start(index) + offset);
+        let col = (0..side).map(|row| g.col_of(index) + row * side);
+        let origin = g.block_origin(index);
+        let block = (0..side).map(|k| origin + k % g.block_side + (k / g.block_side) * side);
+        let diagonals = (self.variant == Variant::Diagonal)
+            .then(|| sudoku::diagonal_cells(g.block_side, index))
+            .into_iter()
+            .flatten();
+
+        let mut peers: Vec<usize> = row
+            .chain(col)
+            .chain(block)
+            .chain(diagonals)
+            .filter(|&i| i != index && self.sudoku[i] == value)
+            .collect();
+        peers.sort_unstable();
+        peers.dedup();
+        peers
+    }
+
+    /// How many more times each digit (index `d - 1`) can be placed on the board.
+    fn remaining_digits(&self) -> Vec<usize> {
+        let side = self.geometry.side();
+        let mut remaining = vec![side; side];
+        for &v in self.sudoku.iter().filter(|&&v| v != 0) {
+            remaining[v as usize - 1] = remaining[v as usize - 1].saturating_sub(1);
+        }
+        remaining
+    }
+
+    /// Whether the digit in `index` counts as a mistake: wrong, and repeating
+    /// a peer unless instant-wrong mode flags it anyway. Never in freeform mode.
+    fn is_mistake(&self, index: usize) -> bool {
+        !self.settings.freeform
+            && self.sudoku[index] != self.solved[index]
+            && (self.settings.instant_wrong || self.has_conflict(index))
+    }
+
+    /// A wrong guess in `index` that instant-wrong mode keeps flagged.
+    fn shows_wrong(&self, index: usize) -> bool {
+        self.settings.instant_wrong
+            && !self.settings.freeform
+            && !self.is_clue[index]
+            && self.sudoku[index] != 0
+            && self.sudoku[index] != self.solved[index]
+    }
+
+    /// Mark every user-entered digit that repeats within its row/col/block.
+    ///
+    /// Clues are never marked; the generator guarantees they are consistent.
+    /// Freeform mode marks nothing.
+    fn conflicts(&self) -> Vec<bool> {
+        (0..self.geometry.cell_count())
+            .map(|i| !self.settings.freeform && !self.is_clue[i] && self.has_conflict(i))
+            .collect()
+    }
+
+    /// Apply a user guess to `index`, which may already hold an earlier guess.
+    fn apply_guess(&mut self, index: usize, digit: i8) {
+        if self.is_clue[index] {
+            return;
+        }
+
+        // Entering the digit a cell already holds takes it out again, if the
+        // player wants that. Otherwise nothing changes, the fail flags included.
+        if self.sudoku[index] == digit {
+            if self.settings.retype_clears {
+                self.start_clock();
+                self.clear_cell(index);
+            }
+            return;
+        }
+        self.start_clock();
+
+        // A legal but wrong digit can leave the puzzle unsolvable without any
+        // conflict showing; strict mode never lets it onto the board.
+        if self.settings.strict && digit != self.solved[index] {
+            self.flash = Some((index, false, Instant::now()));
+            self.guesses += 1;
+            self.rejected = Some((index, digit));
+            self.count_fail();
+            return;
+        }
+
+        let struck = if self.settings.auto_strike {
+            self.strike_note(index, digit)
+        } else {
+            Vec::new()
+        };
+        self.record_edit(index, self.sudoku[index], digit, struck);
+        self.sudoku[index] = digit;
+        self.notes[index] = [false; MAX_SIDE];
+        self.center_notes[index] = [false; MAX_SIDE];
+        self.guesses += 1;
+        if digit == self.solved[index] {
+            self.correct_guesses += 1;
+        }
+        // The flash and the completion glow would both tell right from wrong.
+        if !self.settings.freeform {
+            if digit == self.solved[index] {
+                self.mark_completed_units(index);
+            }
+            self.flash = Some((index, digit == self.solved[index], Instant::now()));
+        }
+        // Recount rather than adjust: the old value may have been empty or a guess.
+        self.recompute_voids_and_maybe_stop_timer();
+
+        // A failed guess overwritten here takes its fail flag with it.
+        self.fail = None;
+        self.collision = false;
+
+        // Only treat as a failure if it's not the correct solution and it conflicts,
+        // or at once in instant-wrong mode.
+        if self.is_mistake(index) {
+            self.collision = true;
+            self.fail = Some(index);
+            self.conflict_peers = self.conflicting_peers(index);
+            self.count_fail();
+        } else if self.settings.freeform || digit == self.solved[index] {
+            // In freeform mode every placement clicks, so the sound gives nothing away.
+            self.play(Effect::Place);
+        }
+
+        self.refresh_same_digit();
+    }
+
+    /// Add a mistake and end the game if that reaches the fail limit.
+    fn count_fail(&mut self) {
+        self.play(Effect::Fail);
+        self.fails += 1;
+        if self.settings.max_fails.is_some_and(|max| self.fails >= max) {
+            self.game_over = true;
+            self.stopped_time = Some(self.active_duration().as_secs());
+        }
+    }
+
+    /// Erase the user entry in `index`.
+    fn clear_cell(&mut self, index: usize) {
+        if self.is_clue[index] {
+            return;
+        }
+
+        self.record_edit(index, self.sudoku[index], 0, Vec::new());
+        self.sudoku[index] = 0;
+        self.recompute_voids_and_maybe_stop_timer();
+
+        if self.fail == Some(index) {
+            self.fail = None;
+        }
+        self.collision = false;
+
+        self.refresh_same_digit();
+    }
+
+    /// Clear the note for `digit` in the peers of `index`; returns the cells that had it.
+    fn strike_note(&mut self, index: usize, digit: i8) -> Vec<usize> {
+        let struck: Vec<usize> = self
+            .unit_cells(index)
+            .into_iter()
+            .filter(|&i| i != index && self.notes[i][digit as usize - 1])
+            .collect();
+        self.set_note(&struck, digit, false);
+        struck
+    }
+
+    /// Set or clear the note for `digit` in each of `cells`.
+    fn set_note(&mut self, cells: &[usize], digit: i8, noted: bool) {
+        for &i in cells {
+            self.notes[i][digit as usize - 1] = noted;
+        }
+    }
+
+    /// Flip the corner note for `digit` in an empty, non-clue cell.
+    fn toggle_note(&mut self, index: usize, digit: i8) {
+        if self.is_clue[index] || self.sudoku[index] != 0 {
+            return;
+        }
+        let note = &mut self.notes[index][digit as usize - 1];
+        *note = !*note;
+        self.unsaved = true;
+    }
+
+    /// Flip the center note for `digit` in an empty, non-clue cell.
+    fn toggle_center_note(&mut self, index: usize, digit: i8) {
+        if self.is_clue[index] || self.sudoku[index] != 0 {
+            return;
+        }
+        let note = &mut self.center_notes[index][digit as usize - 1];
+        *note = !*note;
+        self.unsaved = true;
+    }
+
+    /// Note every digit that can still go into each empty cell, replacing
+    /// the notes there; rerun it to drop candidates ruled out since.
+    fn auto_notes(&mut self) {
+        let side = self.geometry.side();
+        for index in 0..self.geometry.cell_count() {
+            if self.is_clue[index] || self.sudoku[index] != 0 {
+                continue;
+            }
+            let candidates = self.candidates(index);
+            for digit in 1..=side as i8 {
+                self.notes[index][digit as usize - 1] = candidates & digit_bit(digit) != 0;
+            }
+        }
+        self.unsaved = true;
+    }
+
+    /// Whether digits entered now toggle notes rather than place a guess.
+    fn noting(&self) -> bool {
+        self.note_mode != NoteMode::Off || self.note_selection
+    }
+
+    /// Route a number-pad digit to a corner or center note toggle, or a guess.
+    fn enter_digit(&mut self, index: usize, digit: i8) {
+        match self.note_mode {
+            NoteMode::Center => self.toggle_center_note(index, digit),
+            _ if self.noting() => self.toggle_note(index, digit),
+            _ => {
+                self.apply_guess(index, digit);
+                let placed = self.sudoku[index] == digit && self.fail.is_none();
+                if self.settings.auto_advance && placed {
+                    self.advance_selection(index);
+                }
+            }
+        }
+    }
+
+    /// The first empty, non-clue cell after `from` in row-major order, wrapping
+    /// at the end of the board; `None` once no empty cell remains.
+    fn next_empty_cell(&self, from: usize) -> Option<usize> {
+        let cells = self.sudoku.len();
+        (1..=cells)
+            .map(|step| (from + step) % cells)
+            .find(|&i| self.sudoku[i] == 0 && !self.is_clue[i])
+    }
+
+    /// Select the next empty cell after `from`; a full board keeps the selection.
+    fn advance_selection(&mut self, from: usize) {
+        if let Some(next) = self.next_empty_cell(from) {
+            self.select_cell(next);
+        }
+    }
+
+    /// Switch digit entry from guesses to corner notes to center notes, and back.
+    fn cycle_note_mode(&mut self) {
+        let modes = NoteMode::ALL;
+        let current = modes
+            .iter()
+            .position(|&mode| mode == self.note_mode)
+            .unwrap_or(0);
+        self.note_mode = modes[(current + 1) % modes.len()];
+    }
+
+    fn clear_highlight(&mut self) {
+        self.highlight.fill(false);
+    }
+
+    /// Mark all cells sharing the digit of the selected cell; empty cells mark nothing.
+    ///
+    /// A pen digit takes its place whatever the highlight mode, and also marks
+    /// the empty cells it fits into: that is where the next taps go.
+    fn refresh_same_digit(&mut self) {
+        let pen = self.active_digit.filter(|_| self.pen_mode);
+        let value = match (pen, self.settings.highlight_mode) {
+            (Some(digit), _) => digit,
+            (None, HighlightMode::None) => 0,
+            (None, _) => self.selected_cell.map_or(0, |index| self.sudoku[index]),
+        };
+        self.highlight_same = self
+            .sudoku
+            .iter()
+            .map(|&v| value != 0 && v == value)
+            .collect();
+        self.highlight_fits = (0..self.sudoku.len())
+            .map(|i| pen.is_some_and(|digit| self.candidates(i) & digit_bit(digit) != 0))
+            .collect();
+    }
+
+    /// Switch digit-first input on or off; off drops the pen digit.
+    fn toggle_pen_mode(&mut self) {
+        self.pen_mode = !self.pen_mode;
+        self.active_digit = None;
+        self.refresh_same_digit();
+    }
+
+    /// Cells sharing a row, column or block with `index`, `index` included,
+    /// and a diagonal in the diagonal variant; sorted and without repeats.
+    fn unit_cells(&self, index: usize) -> Vec<usize> {
+        let g = self.geometry;
+        let side = g.side();
+        let mut cells = Vec::with_capacity(3 * side);
+
+        // Row
+        let start = g.row_start(index);
+        cells.extend(start..start + side);
+
+        // Column
+        let col = g.col_of(index);
+        cells.extend((0..side).map(|row| col + row * side));
+
+        // Block
+        let origin = g.block_origin(index);
+        for br in 0..g.block_side {
+            for bc in 0..g.block_side {
+                cells.push(origin + bc + br * side);
+            }
+        }
+
+        // Diagonals
+        if self.variant == Variant::Diagonal {
+            cells.extend(sudoku::diagonal_cells(g.block_side, index));
+        }
+
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    /// Highlight the area around `index` that `highlight_mode` asks for.
+    fn highlight_row_col_block(&mut self, index: usize) {
+        self.clear_highlight();
+        let g = self.geometry;
+        let cells = match self.settings.highlight_mode {
+            HighlightMode::RowColBlock => self.unit_cells(index),
+            HighlightMode::RowCol => {
+                let (start, col) = (g.row_start(index), g.col_of(index));
+                (start..start + g.side())
+                    .chain((0..g.side()).map(|row| col + row * g.side()))
+                    .collect()
+            }
+            HighlightMode::SameDigit | HighlightMode::None => Vec::new(),
+        };
+        for i in cells {
+            self.highlight[i] = true;
+        }
+    }
+
+    fn select_cell(&mut self, index: usize) {
+        self.start_clock();
+        if self.settings.auto_clear_fail {
+            self.clear_last_fail();
+        } else {
+            // The wrong digit stays, marked by its conflict; only the flags
+            // describing the last guess go.
+            self.fail = None;
+            self.collision = false;
+        }
+        self.conflict_peers.clear();
+
+        self.selected_cell = Some(index);
+        self.note_selection = false;
+
+        self.highlight_row_col_block(index);
+        self.refresh_same_digit();
+    }
+
+    /// Select `index` on a tap; in pen mode, place the pen digit there too.
+    fn tap_cell(&mut self, index: usize) {
+        if let Some(digit) = self.active_digit.filter(|_| self.pen_mode) {
+            self.select_cell(index);
+            self.enter_digit(index, digit);
+            return;
+        }
+        self.select_cell(index);
+    }
+
+    /// A pointer went down on cell `index` at `now`.
+    fn press_cell(&mut self, index: usize, now: Instant) {
+        self.pressed = Some((index, now));
+    }
+
+    /// A pointer was released over cell `index` at `now`, with the right
+    /// button if `secondary`. Only a press that began on the same cell counts:
+    /// held for `LONG_PRESS_MS` it opens the digit picker, as touch screens
+    /// have no right click; anything shorter is a click. A digit dragged here
+    /// from the number pad is dropped into the cell instead.
+    fn release_cell(&mut self, index: usize, secondary: bool, now: Instant) {
+        if let Some(digit) = self.dragged.take() {
+            self.pressed = None;
+            self.select_cell(index);
+            self.enter_digit(index, digit);
+            return;
+        }
+        let Some((pressed, at)) = self.pressed.take() else {
+            return;
+        };
+        if pressed != index {
+            return;
+        }
+        if secondary {
+            self.select_cell_for_notes(index);
+        } else if now.duration_since(at) >= Duration::from_millis(LONG_PRESS_MS) {
+            self.select_cell(index);
+            if !self.is_clue[index] {
+                self.picker = Some(index);
+            }
+        } else {
+            self.click_cell(index, now);
+        }
+    }
+
+    /// A click on `index` at `now`. A second click on the same empty cell
+    /// within `DOUBLE_CLICK_MS` asks whether to reveal it as a hint, as a
+    /// quick double tap is easily made by accident; any other click is a tap.
+    fn click_cell(&mut self, index: usize, now: Instant) {
+        let double = self.last_click.is_some_and(|(i, at)| {
+            i == index && now.duration_since(at) < Duration::from_millis(DOUBLE_CLICK_MS)
+        });
+        // A third click starts over rather than counting as another double click.
+        self.last_click = (!double).then_some((index, now));
+        if double && !self.is_clue[index] && self.sudoku[index] == 0 {
+            self.select_cell(index);
+            self.confirm_reveal = Some(index);
+        } else {
+            self.tap_cell(index);
+        }
+    }
+
+    /// Press `digit` on the number pad: enter it into the selected cell.
+    ///
+    /// In pen mode the pressed digit becomes the pen digit instead; pressing
+    /// it again puts the pen down.
+    fn press_pad_digit(&mut self, digit: i8) {
+        if self.pen_mode {
+            self.active_digit = (self.active_digit != Some(digit)).then_some(digit);
+            self.refresh_same_digit();
+            return;
+        }
+        if let Some(index) = self.selected_cell {
+            self.enter_digit(index, digit);
+        }
+    }
+
+    /// A pointer went down on `digit` on the number pad, which now follows it
+    /// until it is released.
+    fn grab_pad_digit(&mut self, digit: i8) {
+        self.dragged = Some(digit);
+    }
+
+    /// A pointer was released over `digit` on the number pad. Released where
+    /// it was grabbed, the digit was pressed rather than dragged.
+    fn release_pad_digit(&mut self, digit: i8) {
+        if self.dragged.take() == Some(digit) {
+            self.press_pad_digit(digit);
+        }
+    }
+
+    /// A pointer was released anywhere in the window, after the cell or pad
+    /// digit beneath it had its turn: a drag that missed the board is dropped.
+    fn release_pointer(&mut self) {
+        self.dragged = None;
+        self.pressed = None;
+    }
+
+    /// Enter `digit` through the picker, or toggle its note in note mode.
+    fn pick_digit(&mut self, digit: i8) {
+        let Some(index) = self.picker else {
+            return;
+        };
+        self.enter_digit(index, digit);
+        // Notes often come several at a time, so the picker stays for them.
+        if !self.noting() {
+            self.picker = None;
+        }
+    }
+
+    /// Erase the cell the picker is open for, and close it.
+    fn pick_clear(&mut self) {
+        if let Some(index) = self.picker.take() {
+            self.clear_cell(index);
+        }
+    }
+
+    /// Select `index` for pencil marks: digits toggle its notes, whatever the note mode.
+    fn select_cell_for_notes(&mut self, index: usize) {
+        self.select_cell(index);
+        self.note_selection = true;
+    }
+
+    /// Move the selection by one cell, clues included. Moves off the board
+    /// are ignored rather than wrapped; without a selection the first cell
+    /// is selected.
+    fn move_selection(&mut self, d_row: isize, d_col: isize) {
+        let Some(from) = self.selected_cell else {
+            self.select_cell(0);
+            return;
+        };
+
+        let g = self.geometry;
+        let side = g.side() as isize;
+        let row = g.row_of(from) as isize + d_row;
+        let col = g.col_of(from) as isize + d_col;
+        if (0..side).contains(&row) && (0..side).contains(&col) {
+            self.select_cell((row * side + col) as usize);
+        }
+    }
+
+    /// Act on a key: digits go into the selected cell, as from the pad, and
+    /// the arrow keys move the selection. F1 opens the help and Escape closes
+    /// whatever overlay is open; while one is, the board ignores keys.
+    fn handle_key(&mut self, key: KeyInput) {
+        match key {
+            KeyInput::Help => {
+                self.help_open = true;
+                return;
+            }
+            KeyInput::Escape => {
+                self.help_open = false;
+                self.history_open = false;
+                self.picker = None;
+                self.dragged = None;
+                self.confirm_reveal = None;
+                #[cfg(feature = "solution-explorer")]
+                {
+                    self.explorer = None;
+                }
+                return;
+            }
+            _ if !self.playable()
+                || self.help_open
+                || self.history_open
+                || self.picker.is_some()
+                || self.confirm_reveal.is_some() =>
+            {
+                return;
+            }
+            _ => {}
+        }
+        match key {
+            KeyInput::Digit(digit) => {
+                if let Some(index) = self.selected_cell
+                    && digit as usize <= self.geometry.side()
+                {
+                    self.enter_digit(index, digit);
+                }
+            }
+            KeyInput::Clear => {
+                if let Some(index) = self.selected_cell {
+                    self.clear_cell(index);
+                }
+            }
+            KeyInput::Up => self.move_selection(-1, 0),
+            KeyInput::Down => self.move_selection(1, 0),
+            KeyInput::Left => self.move_selection(0, -1),
+            KeyInput::Right => self.move_selection(0, 1),
+            KeyInput::Help | KeyInput::Escape => {}
+        }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIFFICULTY)
+    }
+}
+
+// --- Timer task -------------------------------------------------------------------------------
+
+/// Body of the background timer: hand the view a reply channel through `send`,
+/// sleep for the delay it answers with, and repeat.
+///
+/// A failed send is retried a few times with a growing pause, so a transient
+/// hiccup doesn't stop the clock for good. If every attempt fails the app no
+/// longer takes messages; `live` is cleared so the view can say so.
+async fn run_timer<E>(
+    mut send: impl FnMut(oneshot::Sender<Duration>) -> Result<(), E>,
+    live: &AtomicBool,
+) {
+    let mut failures = 0;
+    loop {
+        let (reply, delay) = oneshot::channel();
+        if send(reply).is_err() {
+            failures += 1;
+            if failures == TICK_SEND_ATTEMPTS {
+                live.store(false, Ordering::Relaxed);
+                return;
+            }
+            time::sleep(TICK_RETRY_DELAY * 2u32.pow(failures - 1)).await;
+            continue;
+        }
+        failures = 0;
+        live.store(true, Ordering::Relaxed);
+        // The reply is only dropped unanswered when the task is being torn down.
+        let Ok(delay) = delay.await else {
+            return;
+        };
+        time::sleep(delay).await;
+    }
+}
+
+// --- Views ------------------------------------------------------------------------------------
+
+/// Where the game in progress is kept between sessions.
+fn save_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("xilem-sudoku").join(SAVE_FILE_NAME))
+}
+
+/// Where win statistics are kept.
+fn stats_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("xilem-sudoku").join(STATS_FILE_NAME))
+}
+
+/// Where display preferences are kept.
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("xilem-sudoku").join(SETTINGS_FILE_NAME))
+}
+
+/// Bit `digit - 1` of a candidate mask; no bit for 0, an empty cell.
+fn digit_bit(digit: i8) -> u16 {
+    match digit {
+        0 => 0,
+        d => 1 << (d - 1),
+    }
+}
+
+fn format_time(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Decode a Masonry key into a board command.
+fn key_input(key: &Key) -> Option<KeyInput> {
+    match key {
+        Key::Character(c) => match c.as_str() {
+            "0" => Some(KeyInput::Clear),
+            d @ ("1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9") => {
+                Some(KeyInput::Digit(d.as_bytes()[0] as i8 - b'0' as i8))
+            }
+            _ => None,
+        },
+        Key::Named(NamedKey::Backspace | NamedKey::Delete) => Some(KeyInput::Clear),
+        Key::Named(NamedKey::ArrowUp) => Some(KeyInput::Up),
+        Key::Named(NamedKey::ArrowDown) => Some(KeyInput::Down),
+        Key::Named(NamedKey::ArrowLeft) => Some(KeyInput::Left),
+        Key::Named(NamedKey::ArrowRight) => Some(KeyInput::Right),
+        Key::Named(NamedKey::F1) => Some(KeyInput::Help),
+        Key::Named(NamedKey::Escape) => Some(KeyInput::Escape),
+        _ => None,
+    }
+}
+
+fn number_grid(state: &mut AppState, vertical: bool) -> impl WidgetView<AppState> + use<> {
+    let remaining = state.remaining_digits();
+    let side = state.geometry.side();
+    let font_scale = state.settings.font_scale as f32;
+    let glyphs = state.settings.glyphs;
+
+    // Nothing to erase in a clue or an empty cell.
+    let erasable = state
+        .selected_cell
+        .is_some_and(|index| !state.is_clue[index] && state.sudoku[index] != 0);
+
+    // Digit buttons and a final erase button (explicit loop instead of
+    // iterator `.map()` to avoid ICE). They share one closure type, so the
+    // erase button is the `None` entry.
+    let mut number_cells = Vec::new();
+    for i in 0..=side as i32 {
+        let digit = (i < side as i32).then_some(i as i8 + 1);
+        let (symbol, badge, enabled) = match digit {
+            Some(digit) => {
+                let left = remaining[i as usize];
+                (
+                    glyphs.glyph(digit).to_string(),
+                    format!("{left} left"),
+                    left != 0,
+                )
+            }
+            None => ("⌫".to_string(), "erase".to_string(), erasable),
+        };
+        let color = if enabled {
+            GUESS_TEXT_COLOR
+        } else {
+            CLUE_TEXT_COLOR
+        };
+        let content = flex_col((
+            label(symbol)
+                .text_size(font_scale * CELL_TEXT_SIZE * state.geometry.text_scale().min(1.0))
+                .color(color),
+            label(badge)
+                .text_size(font_scale * BADGE_TEXT_SIZE)
+                .color(color),
+        ))
+        .main_axis_alignment(MainAxisAlignment::Center);
+        let held = digit.is_some()
+            && (digit == state.dragged || (state.pen_mode && digit == state.active_digit));
+        // A digit is grabbed on press and either released here, a plain press,
+        // or dropped on a board cell, whose own release handler places it.
+        let btn = input_area(
+            sized_box(content)
+                .padding(0.0)
+                .background_color(if held {
+                    SELECTED_BACKGROUND_COLOR
+                } else {
+                    SOURCE_BG
+                })
+                .corner_radius(0.0)
+                .border_color(Color::TRANSPARENT),
+            move |state: &mut AppState, input: Input| {
+                if !enabled || !state.playable() {
+                    return;
+                }
+                match (digit, input) {
+                    (Some(digit), Input::Down(_)) => state.grab_pad_digit(digit),
+                    (Some(digit), Input::Up(_)) => state.release_pad_digit(digit),
+                    (None, Input::Up(_)) => {
+                        if let Some(index) = state.selected_cell {
+                            state.clear_cell(index);
+                        }
+                    }
+                    _ => {}
+                }
+            },
+        );
+        number_cells.push(if vertical {
+            btn.grid_pos(0, i)
+        } else {
+            btn.grid_pos(i, 0)
+        });
+    }
+
+    let count = side as i32 + 1;
+    if vertical {
+        grid(number_cells, 1, count).gap(GRID_GAP)
+    } else {
+        grid(number_cells, count, 1).gap(GRID_GAP)
+    }
+}
+
+/// Lay out the active notes of a cell as a block of text shaped like a board
+/// block, e.g. 3×3 on the classic board.
+///
+/// Inactive digits are replaced by a figure space (U+2007), which has the
+/// width of a digit, so the remaining notes keep their mini-grid position.
+fn notes_text(notes: &[bool], geometry: Geometry, glyphs: GlyphSet) -> String {
+    let side = geometry.side();
+    let mut text = String::with_capacity(2 * side * 3);
+    for (i, &active) in notes[..side].iter().enumerate() {
+        if i != 0 {
+            text.push(if i % geometry.block_side == 0 {
+                '\n'
+            } else {
+                ' '
+            });
+        }
+        text.push(if active {
+            glyphs.glyph(i as i8 + 1)
+        } else {
+            '\u{2007}'
+        });
+    }
+    text
+}
+
+/// The active notes of a cell as a plain run of digits, e.g. "137".
+fn notes_digits(notes: &[bool], geometry: Geometry, glyphs: GlyphSet) -> String {
+    (0..geometry.side())
+        .filter(|&i| notes[i])
+        .map(|i| glyphs.glyph(i as i8 + 1))
+        .collect()
+}
+
+/// Blend `amount` (0 to 1) of `tint` into `base`.
+fn mix(base: Color, tint: Color, amount: f32) -> Color {
+    Color::new(std::array::from_fn(|i| {
+        base.components[i] + (tint.components[i] - base.components[i]) * amount
+    }))
+}
+
+fn cell(state: &mut AppState, index: usize, conflict: bool) -> impl WidgetView<AppState> + use<> {
+    // A digit turned down by strict mode is shown in place until the next move.
+    let rejected = state.rejected.is_some_and(|(i, _)| i == index);
+    let value = match state.rejected {
+        Some((i, digit)) if i == index => digit,
+        _ => state.sudoku[index],
+    };
+    let show_center = value == 0 && state.active && state.center_notes[index].contains(&true);
+    let show_notes = value == 0 && (show_center || state.notes[index].contains(&true));
+    let glyphs = state.settings.glyphs;
+
+    let flagged = !state.is_clue[index]
+        && !state.revealed[index]
+        && (conflict
+            || rejected
+            || state.wrong[index]
+            || state.shows_wrong(index)
+            || (value != 0 && state.selected_cell == Some(index) && state.collision));
 
     let text = match value {
+        // Hide the board while paused so pausing can't be used to think for free.
+        _ if !state.active => String::new(),
+        0 if show_notes => notes_text(&state.notes[index], state.geometry, glyphs),
         0 => String::new(),
-        n => n.to_string(),
+        // Don't rely on hue alone to tell conflicts apart.
+        n if flagged && state.settings.colorblind => {
+            format!("{CONFLICT_GLYPH}{}", glyphs.glyph(n))
+        }
+        n => glyphs.glyph(n).to_string(),
+    };
+
+    let color = if show_notes {
+        NOTE_TEXT_COLOR
+    } else if state.is_clue[index] {
+        CLUE_TEXT_COLOR
+    } else if state.revealed[index] {
+        REVEALED_TEXT_COLOR
+    } else if flagged {
+        FAIL_TEXT_COLOR
+    } else {
+        GUESS_TEXT_COLOR
+    };
+
+    let flash = state.flash_level(index);
+    let hinted = state.hint.is_some_and(|(i, at)| {
+        i == index && at.elapsed() < Duration::from_millis(HINT_HIGHLIGHT_MS)
+    });
+
+    let explained = state.explanation.as_ref();
+    let background = if hinted || explained.is_some_and(|e| e.target == Some(index)) {
+        HINT_BACKGROUND_COLOR
+    } else if state.selected_cell == Some(index) {
+        SELECTED_BACKGROUND_COLOR
+    } else if explained.is_some_and(|e| e.cells.contains(&index)) {
+        EXPLAIN_BACKGROUND_COLOR
+    } else if state.settings.heatmap {
+        // Fewer candidates burn warmer: those are the cells to attack first.
+        match state.candidate_count(index) {
+            0 => SUDOKU_BACKGROUND_COLOR,
+            n => mix(SUDOKU_BACKGROUND_COLOR, HEATMAP_COLOR, 1.0 / n as f32),
+        }
+    } else if state.highlight_same[index] {
+        SAME_DIGIT_BACKGROUND_COLOR
+    } else if state.highlight_fits[index] {
+        PEN_FITS_BACKGROUND_COLOR
+    } else if state.highlight[index] {
+        SUDOKU_HIGHLIGHT_COLOR
+    } else if state.variant == Variant::Diagonal && state.geometry.on_diagonal(index) {
+        // Tint the diagonals so the extra rule is visible at a glance.
+        DIAGONAL_BACKGROUND_COLOR
+    } else {
+        SUDOKU_BACKGROUND_COLOR
+    };
+    let background = match state.completion_level(index) {
+        Some(level) => mix(background, COMPLETION_COLOR, level),
+        None => background,
+    };
+    let background = match flash {
+        Some((true, level)) => mix(background, FLASH_CORRECT_COLOR, level),
+        Some((false, level)) => mix(background, FLASH_WRONG_COLOR, level),
+        None => background,
+    };
+
+    let cell_label = label(text)
+        .text_alignment(TextAlign::Center)
+        .text_size(
+            state.settings.font_scale as f32
+                * state.geometry.text_scale()
+                * if show_notes {
+                    NOTE_TEXT_SIZE
+                } else {
+                    CELL_TEXT_SIZE
+                },
+        )
+        .weight(if state.is_clue[index] {
+            CLUE_FONT_WEIGHT
+        } else {
+            FontWeight::NORMAL
+        })
+        .color(color);
+
+    // With center notes, the corner notes shrink to a line along the top.
+    let content = if show_center {
+        let scale = state.settings.font_scale as f32 * state.geometry.text_scale();
+        Either::A(flex_col((
+            label(notes_digits(&state.notes[index], state.geometry, glyphs))
+                .text_size(scale * NOTE_TEXT_SIZE)
+                .color(NOTE_TEXT_COLOR),
+            label(notes_digits(
+                &state.center_notes[index],
+                state.geometry,
+                glyphs,
+            ))
+            .text_size(scale * CENTER_NOTE_TEXT_SIZE)
+            .color(NOTE_TEXT_COLOR),
+        )))
+    } else {
+        Either::B(cell_label)
+    };
+
+    let content = sized_box(flex_col((content,)).main_axis_alignment(MainAxisAlignment::Center))
+        .padding(0.0)
+        .background_color(background)
+        .corner_radius(0.0)
+        // The selection stays visible over any highlight or flash, clues included,
+        // for those moving around with the arrow keys.
+        .border_color(if state.selected_cell == Some(index) {
+            FOCUS_RING_COLOR
+        } else if state.conflict_peers.contains(&index) {
+            CONFLICT_PEER_COLOR
+        } else {
+            Color::TRANSPARENT
+        })
+        .border_width(FOCUS_RING_WIDTH);
+
+    // Not a button: a long press is told apart from a tap by when the press
+    // began, which buttons don't report. A right click selects the cell for
+    // notes, sparing a trip to the note mode button.
+    input_area(content, move |state: &mut AppState, input: Input| {
+        if !state.playable() {
+            return;
+        }
+        match input {
+            Input::Down(_) => state.press_cell(index, Instant::now()),
+            Input::Up(pointer) => state.release_cell(
+                index,
+                pointer == Some(PointerButton::Secondary),
+                Instant::now(),
+            ),
+            Input::Key(_) => {}
+        }
+    })
+}
+
+fn info_bar(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let elapsed = format_time(state.clock_seconds());
+    let clock = match state.timer_mode {
+        Timer::CountUp => "Time",
+        Timer::CountDown(_) => "Left",
+    };
+    let best = match state.stats.best_time(state.level) {
+        Some(best) if state.new_record => format!("Best: {} (new record!)", format_time(best)),
+        Some(best) => format!("Best: {}", format_time(best)),
+        None => "Best: –".to_string(),
+    };
+
+    flex_row((
+    FlexSpacer::Fixed(DEFAULT_GAP),
+        label(if state.timer_live.load(Ordering::Relaxed) {
+            format!("{clock}: {elapsed}")
+        } else {
+            format!("{clock}: {elapsed} (stopped)")
+        })
+        .font(FontStack::Source("monospace".into()))
+        .color(match state.ahead_of_best_pace() {
+            Some(true) => PACE_AHEAD_COLOR,
+            Some(false) => PACE_BEHIND_COLOR,
+            None => GUESS_TEXT_COLOR,
+        }),
+        FlexSpacer::Flex(1.0),
+        label(best),
+        FlexSpacer::Flex(1.0),
+        (
+            label(format!("Voids left: {}", state.voids)),
+            state.settings.minimap.then(|| minimap(state)),
+        ),
+                FlexSpacer::Flex(1.0),
+        label(format!("Fails: {}", state.fails)),
+                FlexSpacer::Flex(1.0),
+        label(format!("Moves: {}", state.guesses)),
+                FlexSpacer::Flex(1.0),
+        label(match state.accuracy() {
+            Some(accuracy) => format!("Accuracy: {accuracy:.0}%"),
+            None => "Accuracy: –".to_string(),
+        }),
+                FlexSpacer::Flex(1.0),
+        label(format!("Hints: {}", state.hints_used)),
+                FlexSpacer::Flex(1.0),
+        (
+        label(format!("Clues: {}", state.is_clue.iter().filter(|&&c| c).count())),
+        FlexSpacer::Flex(1.0),
+        state.import_error.clone().map(|err| label(err).color(FAIL_TEXT_COLOR)),
+        state.generation_error.clone().map(|err| label(err).color(FAIL_TEXT_COLOR)),
+        difficulty_presets(state),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        label(format!("Difficulty: {} ({})", state.level, state.rating)),
+                //FlexSpacer::Flex(1.0),
+        //sized_box(
+            slider(
+                0.0,
+                sudoku::MAX_DIFFICULTY_LEVEL as f64,
+                state.difficulty,
+                |state: &mut AppState, val| {
+                    state.difficulty = val;
+                },
+            )
+            .step(1.0)
+            .width(80.px()),
+        // The slider only takes effect with the next game; say so when it differs.
+        (state.difficulty as u8 != state.level)
+            .then(|| label(format!("Next: {:.0}", state.difficulty))),
+        //)
+        //.width(40_i32.px()),
+                FlexSpacer::Flex(1.0),
+        text_button("Copy as text", |state: &mut AppState| {
+            copy_to_clipboard(state.board_snapshot().to_string());
+        })
+        .padding(8.0),
+        text_button("Bookmark", |state: &mut AppState| state.bookmark())
+            .padding(8.0)
+            .disabled(!state.playable() || state.stopped_time.is_some()),
+        text_button("Restore", |state: &mut AppState| state.restore_bookmark())
+            .padding(8.0)
+            .disabled(
+                state.bookmark.is_none() || !state.playable() || state.stopped_time.is_some(),
+            ),
+        (
+            text_button("Restart", |state: &mut AppState| state.restart()).padding(8.0),
+            text_button("New Game", |state: &mut AppState| state.request_new_game()).padding(8.0),
+            daily_button(state),
+        ),
+        #[cfg(feature = "sound")]
+        text_button(
+            if state.settings.sound { "Mute" } else { "Unmute" },
+            |state: &mut AppState| {
+                state.settings.sound = !state.settings.sound;
+                state.save_settings();
+            },
+        )
+        .padding(8.0),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        ),
+    ))
+}
+
+/// A dot per cell, shaded by what fills it, for an overview of the board.
+fn minimap(state: &AppState) -> impl WidgetView<AppState> + use<> {
+    let side = state.geometry.side();
+    let mut dots = Vec::with_capacity(state.geometry.cell_count());
+    for index in 0..state.geometry.cell_count() {
+        dots.push(
+            sized_box(label(""))
+                .width(MINIMAP_DOT.px())
+                .height(MINIMAP_DOT.px())
+                .background_color(match state.fill(index) {
+                    Fill::Clue => CLUE_TEXT_COLOR,
+                    Fill::Guess => GUESS_TEXT_COLOR,
+                    Fill::Empty => MINIMAP_EMPTY_COLOR,
+                })
+                .grid_pos((index % side) as i32, (index / side) as i32),
+        );
+    }
+    let extent = (side as f64 * (MINIMAP_DOT + 1.0)).px();
+    sized_box(grid(dots, side as i32, side as i32).gap(MINIMAP_GAP))
+        .width(extent)
+        .height(extent)
+}
+
+/// Button starting the puzzle of the day, highlighted while it is played and
+/// ticked once solved, with the best time for it.
+fn daily_button(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let date = today();
+    let background = if state.daily == Some(date) {
+        SELECTED_BACKGROUND_COLOR
+    } else {
+        SUDOKU_BACKGROUND_COLOR
+    };
+    let text = if state.stats.daily_solved(date) {
+        "Daily ✓"
+    } else {
+        "Daily"
+    };
+    flex_row((
+        text_button(text, |state: &mut AppState| state.request_daily())
+            .padding(8.0)
+            .background_color(background),
+        state
+            .stats
+            .daily_best(date)
+            .map(|best| label(format!("Daily best: {}", format_time(best)))),
+    ))
+}
+
+/// One button per named difficulty; the one of the game in play is highlighted.
+fn difficulty_presets(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let mut buttons = Vec::new();
+    for (name, level) in DIFFICULTY_PRESETS {
+        let background = if state.level == level {
+            SELECTED_BACKGROUND_COLOR
+        } else {
+            SUDOKU_BACKGROUND_COLOR
+        };
+        buttons.push(
+            text_button(name, move |state: &mut AppState| state.start_preset(level))
+                .padding(8.0)
+                .background_color(background),
+        );
+    }
+    flex_row(buttons)
+}
+
+/// Game actions: undo/redo, pause, help and note mode.
+fn controls_bar(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    flex_row((
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        text_button("Undo", |state: &mut AppState| state.undo())
+            .padding(8.0)
+            .disabled(!state.playable() || state.undo_stack.is_empty()),
+        text_button("Redo", |state: &mut AppState| state.redo())
+            .padding(8.0)
+            .disabled(!state.playable() || state.redo_stack.is_empty()),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        text_button(
+            if state.active { "Pause" } else { "Resume" },
+            |state: &mut AppState| state.toggle_pause(),
+        )
+        .padding(8.0)
+        .disabled(state.stopped_time.is_some()),
+        (
+            text_button("Hint", |state: &mut AppState| state.give_hint())
+                .padding(8.0)
+                .disabled(!state.active || state.stopped_time.is_some()),
+            text_button("Explain", |state: &mut AppState| state.explain())
+                .padding(8.0)
+                .disabled(!state.playable() || state.stopped_time.is_some()),
+        ),
+        text_button(
+            format!("Check (+{CHECK_PENALTY_SECS}s)"),
+            |state: &mut AppState| state.check_board(),
+        )
+        .padding(8.0)
+        .disabled(!state.playable() || state.stopped_time.is_some()),
+        text_button("Solve", |state: &mut AppState| state.reveal_solution())
+            .padding(8.0)
+            .disabled(!state.active || state.stopped_time.is_some()),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        (
+            text_button(
+                format!("Notes: {}", state.note_mode.label()),
+                |state: &mut AppState| state.cycle_note_mode(),
+            )
+            .padding(8.0),
+            text_button(
+                if state.pen_mode {
+                    "Pen: On"
+                } else {
+                    "Pen: Off"
+                },
+                |state: &mut AppState| state.toggle_pen_mode(),
+            )
+            .padding(8.0),
+        ),
+        text_button("Auto-notes", |state: &mut AppState| state.auto_notes())
+            .padding(8.0)
+            .disabled(!state.playable() || state.stopped_time.is_some()),
+        FlexSpacer::Flex(1.0),
+        text_button("History", |state: &mut AppState| state.history_open = true).padding(8.0),
+        text_button("?", |state: &mut AppState| state.help_open = true).padding(8.0),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+    ))
+}
+
+/// Player preferences that shape new games and the board display.
+fn options_bar(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    flex_row((
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        text_button(
+            match state.settings.max_fails {
+                Some(max) => format!("Fail limit: {max}"),
+                None => "Fail limit: Off".to_string(),
+            },
+            |state: &mut AppState| state.cycle_fail_limit(),
+        )
+        .padding(8.0),
+        text_button(
+            format!("Highlight: {}", state.settings.highlight_mode.label()),
+            |state: &mut AppState| state.cycle_highlight_mode(),
+        )
+        .padding(8.0),
+        text_button(
+            format!("Timer: {}", state.timer_mode.label()),
+            |state: &mut AppState| state.cycle_timer_mode(),
+        )
+        .padding(8.0)
+        .disabled(state.started && state.stopped_time.is_none()),
+        // Options for new games; nested, as a tuple holds at most 16 views.
+        (
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Symmetric",
+                state.settings.symmetric,
+                |state: &mut AppState, checked| {
+                    state.settings.symmetric = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Minimal",
+                state.settings.minimal,
+                |state: &mut AppState, checked| {
+                    state.settings.minimal = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Logic-only",
+                state.settings.logic_only,
+                |state: &mut AppState, checked| {
+                    state.settings.logic_only = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            board_size_buttons(state),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Diagonal (X)",
+                state.settings.diagonal,
+                |state: &mut AppState, checked| {
+                    state.settings.diagonal = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            text_button(
+                match state.settings.clue_target {
+                    Some(clues) => format!("Clues: {clues}"),
+                    None => "Clues: Auto".to_string(),
+                },
+                |state: &mut AppState| state.cycle_clue_target(),
+            )
+            .padding(8.0),
+        ),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        checkbox(
+            "Auto-strike notes",
+            state.settings.auto_strike,
+            |state: &mut AppState, checked| {
+                state.settings.auto_strike = checked;
+                state.save_settings();
+            },
+        ),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        checkbox(
+            "Auto-clear fails",
+            state.settings.auto_clear_fail,
+            |state: &mut AppState, checked| {
+                state.settings.auto_clear_fail = checked;
+                state.save_settings();
+            },
+        ),
+        (
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Auto-advance",
+                state.settings.auto_advance,
+                |state: &mut AppState, checked| {
+                    state.settings.auto_advance = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Retype clears",
+                state.settings.retype_clears,
+                |state: &mut AppState, checked| {
+                    state.settings.retype_clears = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Colorblind",
+                state.settings.colorblind,
+                |state: &mut AppState, checked| {
+                    state.settings.colorblind = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Strict",
+                state.settings.strict,
+                |state: &mut AppState, checked| {
+                    state.settings.strict = checked;
+                    state.settings.freeform &= !checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Instant wrong",
+                state.settings.instant_wrong,
+                |state: &mut AppState, checked| {
+                    state.settings.instant_wrong = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Freeform",
+                state.settings.freeform,
+                |state: &mut AppState, checked| {
+                    state.settings.freeform = checked;
+                    state.settings.strict &= !checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Heatmap",
+                state.settings.heatmap,
+                |state: &mut AppState, checked| {
+                    state.settings.heatmap = checked;
+                    state.save_settings();
+                },
+            ),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Coordinates",
+                state.settings.coordinates,
+                |state: &mut AppState, checked| {
+                    state.settings.coordinates = checked;
+                    state.save_settings();
+                },
+            ),
+        ),
+        (
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            text_button(
+                format!("Pad: {}", state.settings.pad_position.label()),
+                |state: &mut AppState| state.cycle_pad_position(),
+            )
+            .padding(8.0),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            text_button(
+                format!("Glyphs: {}", state.settings.glyphs.label()),
+                |state: &mut AppState| state.cycle_glyphs(),
+            )
+            .padding(8.0),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            label("Text size"),
+            slider(
+                MIN_FONT_SCALE,
+                MAX_FONT_SCALE,
+                state.settings.font_scale,
+                |state: &mut AppState, val| state.set_font_scale(val),
+            )
+            .step(0.1)
+            .width(80.px()),
+            FlexSpacer::Fixed(DEFAULT_GAP),
+            checkbox(
+                "Mini-map",
+                state.settings.minimap,
+                |state: &mut AppState, checked| {
+                    state.settings.minimap = checked;
+                    state.save_settings();
+                },
+            ),
+            // Only large boards zoom; see `AppState::board_zoom`.
+            (state.geometry.block_side > DEFAULT_BLOCK_SIDE).then(|| {
+                (
+                    FlexSpacer::Fixed(DEFAULT_GAP),
+                    label("Zoom"),
+                    slider(
+                        MIN_BOARD_ZOOM,
+                        MAX_BOARD_ZOOM,
+                        state.settings.board_zoom,
+                        |state: &mut AppState, val| state.set_board_zoom(val),
+                    )
+                    .step(0.25)
+                    .width(80.px()),
+                )
+            }),
+        ),
+        FlexSpacer::Flex(1.0),
+    ))
+}
+
+/// One button per board size; picking a size starts a new game on it.
+fn board_size_buttons(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let mut buttons = Vec::new();
+    for block_side in BOARD_SIZES {
+        let side = block_side * block_side;
+        buttons.push(
+            text_button(format!("{side}×{side}"), move |state: &mut AppState| {
+                state.set_board_size(block_side);
+            })
+            .padding(8.0)
+            .disabled(block_side == state.geometry.block_side),
+        );
+    }
+    flex_row(buttons)
+}
+
+fn copy_to_clipboard(text: String) {
+    // Clipboard access can fail (e.g. no clipboard service); there is nothing useful to do then.
+    if let Ok(mut clipboard) = arboard::Clipboard::new() {
+        let _ = clipboard.set_text(text);
+    }
+}
+
+fn import_bar(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    flex_row((
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        text_input(state.import_text.clone(), |state: &mut AppState, text| {
+            state.import_text = text;
+        })
+        .flex(1.0),
+        text_button("Import", |state: &mut AppState| state.import()).padding(8.0),
+        text_button("Export", |state: &mut AppState| {
+            copy_to_clipboard(state.board_snapshot().to_str_line());
+        })
+        .padding(8.0),
+        #[cfg(feature = "solution-explorer")]
+        text_button("Solutions", |state: &mut AppState| state.open_explorer()).padding(8.0),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+    ))
+}
+
+/// Inputs for writing a printable set of puzzles at the current settings.
+fn worksheet_bar(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    flex_row((
+        FlexSpacer::Fixed(DEFAULT_GAP),
+        label("Worksheet:"),
+        text_input(
+            state.worksheet.count.clone(),
+            |state: &mut AppState, text| {
+                state.worksheet.count = text;
+            },
+        )
+        .width(60.px()),
+        text_input(
+            state.worksheet.path.clone(),
+            |state: &mut AppState, text| {
+                state.worksheet.path = text;
+            },
+        )
+        .flex(1.0),
+        checkbox(
+            "Solutions",
+            state.worksheet.solutions,
+            |state: &mut AppState, checked| {
+                state.worksheet.solutions = checked;
+            },
+        ),
+        text_button("Generate set", |state: &mut AppState| {
+            state.generate_worksheet()
+        })
+        .padding(8.0),
+        state.worksheet.message.clone().map(label),
+        FlexSpacer::Fixed(DEFAULT_GAP),
+    ))
+}
+
+/// Build the full Sudoku board (e.g. 3×3 blocks of 3×3 cells for the classic size).
+fn build_board(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let conflicts = state.conflicts();
+    let block_side = state.geometry.block_side;
+    let side = state.geometry.side();
+    // A square board has as many blocks per row as cells per block row.
+    let board_blocks = block_side;
+    let mut sudoku_blocks = Vec::with_capacity(board_blocks * board_blocks);
+
+    for block_row in 0..board_blocks {
+        for block_col in 0..board_blocks {
+            let origin = block_row * side * block_side + block_col * block_side;
+            sudoku_blocks.push(
+                board_block(state, origin, &conflicts).grid_pos(block_col as i32, block_row as i32),
+            );
+        }
+    }
+
+    grid(sudoku_blocks, board_blocks as i32, board_blocks as i32).gap(GRID_GAP)
+}
+
+/// The board with row letters down its left edge and column numbers along
+/// the top. The labels take one cell's room on each side, so the whole keeps
+/// the board's square shape.
+fn labeled_board(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let side = state.geometry.side();
+    let size = state.settings.font_scale as f32 * BADGE_TEXT_SIZE;
+    let mut labels = Vec::with_capacity(2 * side);
+    for k in 0..side {
+        let column = (k + 1).to_string();
+        let row = char::from(b'A' + k as u8).to_string();
+        for (text, x, y) in [(column, k + 1, 0), (row, 0, k + 1)] {
+            labels.push(
+                label(text)
+                    .text_alignment(TextAlign::Center)
+                    .text_size(size)
+                    .color(COORDINATE_TEXT_COLOR)
+                    .grid_pos(x as i32, y as i32),
+            );
+        }
+    }
+    let n = side as i32 + 1;
+    let board = build_board(state).grid_item(GridParams::new(1, 1, n - 1, n - 1));
+    grid((labels, board), n, n)
+}
+
+/// One framed block of cells, with `origin` the index of its top-left cell.
+fn board_block(
+    state: &mut AppState,
+    origin: usize,
+    conflicts: &[bool],
+) -> impl WidgetView<AppState> + use<> {
+    let block_side = state.geometry.block_side;
+    let side = state.geometry.side();
+    let mut block_cells = Vec::with_capacity(block_side * block_side);
+
+    for cell_row in 0..block_side {
+        for cell_col in 0..block_side {
+            let index = origin + cell_row * side + cell_col;
+            block_cells.push(
+                cell(state, index, conflicts[index]).grid_pos(cell_col as i32, cell_row as i32),
+            );
+        }
+    }
+
+    let block_grid = grid(block_cells, block_side as i32, block_side as i32);
+    sized_box(block_grid)
+        .border_color(BLOCK_BORDER_COLOR)
+        .border_width(BLOCK_BORDER_WIDTH)
+}
+
+/// Modal shown once the mistake limit is reached or the countdown ran out.
+fn game_over_overlay(time_up: bool) -> impl WidgetView<AppState> + use<> {
+    sized_box(
+        flex_col((
+            label("Game Over").text_size(CELL_TEXT_SIZE).color(FAIL_TEXT_COLOR),
+            label(if time_up {
+                "Time's up."
+            } else {
+                "Too many mistakes."
+            }),
+            text_button("New Game", |state: &mut AppState| state.new_game()).padding(8.0),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(FAIL_TEXT_COLOR)
+    .corner_radius(8.0)
+}
+
+/// Modal explaining the rules, the cell colors, the mouse controls and the
+/// keyboard shortcuts.
+fn help_overlay() -> impl WidgetView<AppState> + use<> {
+    sized_box(
+        flex_col((
+            label("How to play").text_size(CELL_TEXT_SIZE),
+            label(
+                "Fill every empty cell so that each row, column and block holds every digit \
+                 exactly once. In X-Sudoku, so do both main diagonals.",
+            ),
+            label("Colors").text_size(BADGE_TEXT_SIZE * 1.5),
+            label("Gray, heavier digits: clues of the puzzle, which can't be changed.").color(CLUE_TEXT_COLOR),
+            label("White: your guesses.").color(GUESS_TEXT_COLOR),
+            label("Red: guesses that conflict with another cell.").color(FAIL_TEXT_COLOR),
+            label("Red outline: the cells whose digit the last conflicting guess repeats.")
+                .color(CONFLICT_PEER_COLOR),
+            label("Yellow outline: the selected cell, moved with the arrow keys.")
+                .color(FOCUS_RING_COLOR),
+            label("Blue: cells filled by a hint or by Solve.").color(REVEALED_TEXT_COLOR),
+            label("Green cell: the one Explain is about; the cells its reasoning uses are tinted.")
+                .color(FLASH_CORRECT_COLOR),
+            label("Small digits: your notes; center notes are the larger ones in the middle.")
+                .color(NOTE_TEXT_COLOR),
+            label("Mouse").text_size(BADGE_TEXT_SIZE * 1.5),
+            // Nested, as a tuple holds at most 16 views.
+            (
+                label("Click a cell, then a pad digit: enter it (or a note in note mode)"),
+                label(
+                    "⌫ on the pad, or the cell's own digit (unless \"Retype clears\" is off): \
+                     clear the cell",
+                ),
+                label("Right-click a cell: digits toggle its notes until another cell is selected"),
+                label("Press and hold a cell: pick a digit from a pad next to it"),
+                label("Double-click an empty cell: reveal its digit as a hint, once confirmed"),
+                label("Drag a pad digit onto a cell: enter it there"),
+                label("Pen mode: choose a pad digit, then click every cell it goes into"),
+            ),
+            label("Keys").text_size(BADGE_TEXT_SIZE * 1.5),
+            (
+                label("1–9: enter a digit into the selected cell (or a note in note mode)"),
+                label("0, Backspace, Delete: clear the selected cell"),
+                label("Arrow keys: move the selection, clues included"),
+                label("F1: show this help; Escape: close it or any other dialog"),
+            ),
+            text_button("Close", |state: &mut AppState| state.help_open = false).padding(8.0),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(SELECTED_BACKGROUND_COLOR)
+    .corner_radius(8.0)
+}
+
+/// Modal listing the recent games at the current difficulty, newest first.
+fn history_overlay(state: &AppState) -> impl WidgetView<AppState> + use<> {
+    let level = state.level;
+    let games: Vec<_> = state
+        .stats
+        .history(level)
+        .rev()
+        .map(|game| {
+            label(format!(
+                "{}  Fails: {}  Score: {}{}",
+                format_time(game.seconds),
+                game.fails,
+                game.score,
+                if game.assisted { "  (assisted)" } else { "" }
+            ))
+        })
+        .collect();
+    sized_box(
+        flex_col((
+            label(format!("Recent games at level {level}")).text_size(CELL_TEXT_SIZE),
+            games
+                .is_empty()
+                .then(|| label("No games solved at this level yet.")),
+            games,
+            text_button("Close", |state: &mut AppState| state.history_open = false).padding(8.0),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(SELECTED_BACKGROUND_COLOR)
+    .corner_radius(8.0)
+}
+
+/// Digit picker for touch screens, opened by a long press on a cell.
+fn picker_overlay(state: &AppState) -> impl WidgetView<AppState> + use<> {
+    let block_side = state.geometry.block_side;
+    let glyphs = state.settings.glyphs;
+    let mut digits = Vec::with_capacity(state.geometry.side());
+    for i in 0..state.geometry.side() {
+        let digit = i as i8 + 1;
+        digits.push(
+            text_button(glyphs.glyph(digit).to_string(), move |state: &mut AppState| {
+                state.pick_digit(digit);
+            })
+            .padding(8.0)
+            .grid_pos((i % block_side) as i32, (i / block_side) as i32),
+        );
+    }
+    let extent = (PICKER_BUTTON_SIZE * block_side as f64).px();
+    sized_box(
+        flex_col((
+            label(if state.noting() {
+                "Toggle notes"
+            } else {
+                "Enter a digit"
+            }),
+            sized_box(grid(digits, block_side as i32, block_side as i32).gap(GRID_GAP))
+                .width(extent)
+                .height(extent),
+            flex_row((
+                text_button("Clear", |state: &mut AppState| state.pick_clear()).padding(8.0),
+                text_button("Close", |state: &mut AppState| state.picker = None).padding(8.0),
+            )),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(SELECTED_BACKGROUND_COLOR)
+    .corner_radius(8.0)
+}
+
+/// Solution explorer: one solution of the board at a time, with buttons to
+/// step through the others.
+#[cfg(feature = "solution-explorer")]
+fn explorer_overlay(explorer: &Explorer, variant: Variant) -> impl WidgetView<AppState> + use<> {
+    let count = explorer.solutions.len();
+    let (title, grid) = match explorer.solutions.get(explorer.shown) {
+        Some(solution) => (
+            format!(
+                "Solution {} of {count}{}",
+                explorer.shown + 1,
+                if count == EXPLORER_LIMIT { " or more" } else { "" }
+            ),
+            sudoku::Sudoku(solution.clone(), Vec::new(), variant).to_string(),
+        ),
+        None => ("The board has no solution".to_string(), String::new()),
+    };
+    sized_box(
+        flex_col((
+            label(title),
+            label(grid).font(FontStack::Source("monospace".into())),
+            flex_row((
+                text_button("Previous", |state: &mut AppState| state.step_explorer(-1))
+                    .padding(8.0),
+                text_button("Next", |state: &mut AppState| state.step_explorer(1)).padding(8.0),
+                text_button("Close", |state: &mut AppState| state.explorer = None).padding(8.0),
+            )),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(SELECTED_BACKGROUND_COLOR)
+    .corner_radius(8.0)
+}
+
+/// Modal asking whether to throw away the game in progress for a new one.
+fn confirm_new_game_overlay() -> impl WidgetView<AppState> + use<> {
+    sized_box(
+        flex_col((
+            label("Discard current game?"),
+            flex_row((
+                text_button("Yes", |state: &mut AppState| state.accept_new_game()).padding(8.0),
+                text_button("No", |state: &mut AppState| state.decline_new_game()).padding(8.0),
+            )),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(SELECTED_BACKGROUND_COLOR)
+    .corner_radius(8.0)
+}
+
+/// Modal asking whether to reveal the cell a double click picked, at the
+/// cost of a hint.
+fn confirm_reveal_overlay() -> impl WidgetView<AppState> + use<> {
+    sized_box(
+        flex_col((
+            label("Reveal this cell? It counts as a hint."),
+            flex_row((
+                text_button("Yes", |state: &mut AppState| state.accept_reveal()).padding(8.0),
+                text_button("No", |state: &mut AppState| state.confirm_reveal = None).padding(8.0),
+            )),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(SELECTED_BACKGROUND_COLOR)
+    .corner_radius(8.0)
+}
+
+/// Modal shown once the puzzle is solved, with the final stats of the game.
+fn solved_overlay(state: &AppState) -> impl WidgetView<AppState> + use<> {
+    let time = format_time(state.elapsed_seconds());
+    sized_box(
+        flex_col((
+            label("Solved!").text_size(CELL_TEXT_SIZE),
+            state.new_record.then(|| label("New best time!")),
+            label(format!("Time: {time}")),
+            label(format!("Fails: {}", state.fails)),
+            label(format!("Hints: {}", state.hints_used)),
+            label(format!("Checks: {}", state.checks_used)),
+            label(format!("Score: {}", state.score())),
+            label(format!("Difficulty: {} ({})", state.level, state.rating)),
+            flex_row((
+                text_button("New Game", |state: &mut AppState| state.new_game()).padding(8.0),
+                text_button("Dismiss", |state: &mut AppState| {
+                    state.solved_dialog_open = false;
+                })
+                .padding(8.0),
+            )),
+        ))
+        .gap(GAP),
+    )
+    .padding(24.0)
+    .background_color(SUDOKU_BACKGROUND_COLOR)
+    .border_color(HINT_BACKGROUND_COLOR)
+    .corner_radius(8.0)
+}
+
+/// The board with the number pad on the side chosen in the settings.
+/// Side of the largest square board that fits its share of a play area of
+/// `space`, with the number pad beside or above/below it.
+fn board_extent(space: Size, beside: bool) -> f64 {
+    let share = |length: f64| (length - GAP_SIZE).max(0.0) * BOARD_FLEX / (BOARD_FLEX + PAD_FLEX);
+    if beside {
+        share(space.width).min(space.height)
+    } else {
+        space.width.min(share(space.height))
+    }
+}
+
+fn play_area(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let position = state.settings.pad_position;
+    let beside = position.is_beside();
+    let pad_first = matches!(position, PadPosition::Top | PadPosition::Left);
+    let board = if state.settings.coordinates {
+        Either::A(labeled_board(state))
+    } else {
+        Either::B(build_board(state))
+    };
+    // Keep the cells square whatever the window's shape. Until the first
+    // layout reports the room there is, the board takes what it gets.
+    // A zoomed board scrolls within that square.
+    let board = if state.play_space.is_zero_area() {
+        Either::A(board)
+    } else {
+        let side = board_extent(state.play_space, beside);
+        let zoomed = (side * state.board_zoom()).px();
+        Either::B(
+            sized_box(portal(sized_box(board).width(zoomed).height(zoomed)))
+                .width(side.px())
+                .height(side.px()),
+        )
+    }
+    .flex(BOARD_FLEX);
+    let (before, after) = if pad_first {
+        (Some(number_grid(state, beside).flex(PAD_FLEX)), None)
+    } else {
+        (None, Some(number_grid(state, beside).flex(PAD_FLEX)))
+    };
+
+    // Room the square board leaves is split evenly around it and the pad.
+    let area = if beside {
+        Either::A(
+            flex_row((before, board, after))
+                .main_axis_alignment(MainAxisAlignment::Center)
+                .gap(GAP),
+        )
+    } else {
+        Either::B(
+            flex_col((before, board, after))
+                .main_axis_alignment(MainAxisAlignment::Center)
+                .gap(GAP),
+        )
+    };
+    resize_observer(
+        |state: &mut AppState, size: Size| state.play_space = size,
+        area,
+    )
+}
+
+fn app_logic(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
+    let layout = flex_col((
+        FlexSpacer::Fixed(GAP),
+        info_bar(state),
+        controls_bar(state),
+        state
+            .explanation
+            .as_ref()
+            .map(|explanation| label(explanation.text.clone())),
+        options_bar(state),
+        import_bar(state),
+        worksheet_bar(state),
+        play_area(state).flex(1.0),
+    ))
+    .gap(GAP);
+
+    let layout = zstack((
+        layout,
+        state.game_over.then(|| game_over_overlay(state.time_up)),
+        state.solved_dialog_open.then(|| solved_overlay(state)),
+        state.confirm_new_game.then(confirm_new_game_overlay),
+        state.confirm_reveal.is_some().then(confirm_reveal_overlay),
+        state.help_open.then(help_overlay),
+        state.history_open.then(|| history_overlay(state)),
+        state.picker.is_some().then(|| picker_overlay(state)),
+        #[cfg(feature = "solution-explorer")]
+        state
+            .explorer
+            .as_ref()
+            .map(|explorer| explorer_overlay(explorer, state.variant)),
+    ));
+
+    // Background task: tick once per second to update the timer label. Each tick
+    // asks the state how long to sleep, so ticks land on the clock's second boundaries.
+    // `task_raw`, as the task captures the shared liveness flag.
+    let app = fork(
+        flash_animation(layout, state),
+        state.active.then(|| {
+            let live = state.timer_live.clone();
+            task_raw(
+                move |proxy, _| {
+                    let live = live.clone();
+                    async move { run_timer(|reply| proxy.message(reply), &live).await }
+                },
+                |state: &mut AppState, reply: oneshot::Sender<Duration>| {
+                    // Re-running the view updates the timer display. Edits are saved here
+                    // within a second; pausing, new games and closing the window save at once.
+                    state.tick();
+                    state.check_countdown();
+                    state.save_game();
+                    let _ = reply.send(state.until_next_tick());
+                },
+            )
+        }),
+    );
+
+    // Poll while a new window size waits to be written.
+    let app = fork(
+        app,
+        state.window_resized.is_some().then(|| {
+            task(
+                |proxy, _| async move {
+                    while proxy.message(()).is_ok() {
+                        time::sleep(WINDOW_SAVE_DELAY).await;
+                    }
+                },
+                |state: &mut AppState, ()| state.save_window_size_if_settled(),
+            )
+        }),
+    );
+
+    // Keys pressed anywhere in the window end up here, unless a text field took
+    // them, and so does every pointer release.
+    let app = input_area(app, |state: &mut AppState, input: Input| match input {
+        Input::Key(key) => {
+            if let Some(key) = key_input(&key) {
+                state.handle_key(key);
+            }
+        }
+        // Cells and pad digits heard a release over them first.
+        Input::Up(_) => state.release_pointer(),
+        Input::Down(_) => {}
+    })
+    .with_keys();
+
+    // The root view fills the window, so its size is the window's inner size.
+    resize_observer(
+        |state: &mut AppState, size: Size| state.record_window_size(size),
+        app,
+    )
+}
+
+/// Run `view` alongside a task that refreshes it every frame while a placement
+/// flash or a completion glow fades.
+fn flash_animation<V: WidgetView<AppState>>(
+    view: V,
+    state: &AppState,
+) -> impl WidgetView<AppState> + use<V> {
+    fork(
+        view,
+        (state.flash.is_some() || !state.completed.is_empty()).then(|| {
+            task(
+                |proxy, _| async move {
+                    while proxy.message(()).is_ok() {
+                        time::sleep(FLASH_FRAME).await;
+                    }
+                },
+                |state: &mut AppState, ()| {
+                    state.expire_flash();
+                    state.expire_completed();
+                },
+            )
+        }),
+    )
+}
+
+fn main() -> Result<(), EventLoopError> {
+    // Settings come first, so the window and a fresh first game follow them.
+    let settings_path = settings_path();
+    let settings = settings_path
+        .as_deref()
+        .map(Settings::load)
+        .unwrap_or_default();
+    let window_options = WindowOptions::new("Sudoku")
+        .with_min_inner_size(LogicalSize::new(MIN_WINDOW_SIDE, MIN_WINDOW_SIDE))
+        .with_initial_inner_size(settings.initial_window_size())
+        .on_close(|state: &mut AppState| {
+            // Edits since the last tick, and the time played since the last save.
+            if state.active && state.stopped_time.is_none() {
+                state.tick();
+                state.unsaved = true;
+            }
+            state.save_game();
+        });
+    let save_path = save_path();
+    let saved = save_path
+        .as_deref()
+        .and_then(|path| AppState::load(path).ok());
+    let mut state = match saved {
+        Some(mut state) => {
+            state.settings = settings;
+            state
+        }
+        None => AppState::with_settings(DEFAULT_DIFFICULTY, settings),
     };
+    state.settings_path = settings_path;
+    state.save_path = save_path;
+    state.stats_path = stats_path();
+    if let Some(path) = &state.stats_path {
+        state.stats = Stats::load(path);
+    }
+    let app = Xilem::new_simple(state, app_logic, window_options);
+
+    app.run_in(EventLoop::with_user_event())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file in the temp directory, removed when dropped so that a failing
+    /// assertion doesn't leave it behind.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let name = format!("xilem-sudoku-{}-{name}", std::process::id());
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl std::ops::Deref for TempFile {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl AsRef<Path> for TempFile {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// A fresh game whose settings are written to a temp file.
+    fn temp_settings(name: &str) -> (AppState, TempFile) {
+        let path = TempFile::new(name);
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.settings_path = Some(path.to_path_buf());
+        (state, path)
+    }
+
+    /// An empty cell and a wrong digit for it that repeats a peer's digit if
+    /// `conflicting` is set, or fits among its peers if not. The cell is the
+    /// last such one, so it lies off any smaller board.
+    fn wrong_digit(state: &AppState, conflicting: bool) -> (usize, i8) {
+        (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0)
+            .rev()
+            .find_map(|i| {
+                (1..=9)
+                    .find(|&d| d != state.solved[i] && state.can_place(i, d) != conflicting)
+                    .map(|d| (i, d))
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.is_clue.iter().position(|&c| !c).unwrap();
+        state.apply_guess(index, state.solved[index]);
+        state.fails = 2;
+
+        let path = TempFile::new("game.json");
+        state.save(&path).unwrap();
+        let loaded = AppState::load(&path).unwrap();
+
+        assert_eq!(loaded.sudoku, state.sudoku);
+        assert_eq!(loaded.solved, state.solved);
+        assert_eq!(loaded.is_clue, state.is_clue);
+        assert_eq!(loaded.voids, state.voids);
+        assert_eq!(loaded.fails, 2);
+        assert_eq!(loaded.elapsed_seconds(), state.elapsed_seconds());
+    }
+
+    #[test]
+    fn lost_game_stays_lost_after_reload() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.timer_mode = Timer::CountDown(Duration::from_secs(60));
+        state.start_clock();
+        state.active_time = Duration::from_secs(60);
+        state.check_countdown();
+        let path = TempFile::new("lost.json");
+        state.save(&path).unwrap();
+        let loaded = AppState::load(&path).unwrap();
+
+        assert!(loaded.game_over && loaded.time_up);
+        assert!(!loaded.playable());
+        assert_eq!(loaded.stopped_time, Some(60));
+    }
+
+    #[test]
+    fn reloaded_game_keeps_help_taken_and_notes() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.give_hint();
+        state.check_board();
+        state.auto_notes();
+        let path = TempFile::new("help.json");
+        state.save(&path).unwrap();
+        let loaded = AppState::load(&path).unwrap();
+
+        assert!(loaded.assisted);
+        assert_eq!((loaded.hints_used, loaded.checks_used), (1, 1));
+        assert_eq!(loaded.revealed, state.revealed);
+        assert_eq!(loaded.notes, state.notes);
+        assert_eq!(loaded.center_notes, state.center_notes);
+    }
+
+    #[test]
+    fn saved_game_with_digits_off_the_board_is_rejected() {
+        let state = AppState::new(DEFAULT_DIFFICULTY);
+        let path = TempFile::new("corrupt.json");
+        state.save(&path).unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        let mut saved: serde_json::Value = serde_json::from_str(&json).unwrap();
+        saved["board"][0] = 10.into();
+        fs::write(&path, saved.to_string()).unwrap();
+        let err = AppState::load(&path).err().unwrap();
+        saved["board"][0] = 0.into();
+        saved["solution"][0] = 0.into();
+        fs::write(&path, saved.to_string()).unwrap();
+        let zero = AppState::load(&path).err().unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(zero.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn autosave_waits_for_an_edit() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let path = TempFile::new("autosave.json");
+        state.autosave(&path);
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+
+        // Clock ticks alone write nothing.
+        state.active_time += Duration::from_secs(5);
+        state.tick();
+        state.autosave(&path);
+        assert!(!path.exists());
+
+        let index = state.is_clue.iter().position(|&c| !c).unwrap();
+        state.apply_guess(index, state.solved[index]);
+        state.autosave(&path);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn pausing_and_new_games_save_at_once() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let path = TempFile::new("pause.json");
+        state.save_path = Some(path.to_path_buf());
+
+        state.active_time += Duration::from_secs(5);
+        state.toggle_pause();
+        assert!(!state.unsaved);
+        let saved = AppState::load(&path).unwrap();
+        assert_eq!(saved.active_time, state.active_time);
+        fs::remove_file(&path).unwrap();
+
+        state.new_game();
+        assert!(path.exists());
+        assert_eq!(state.save_path.as_deref(), Some(&*path));
+    }
+
+    #[test]
+    fn clue_cells_are_selectable_but_not_editable() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        let value = state.sudoku[clue];
+
+        state.select_cell(clue);
+        assert_eq!(state.selected_cell, Some(clue));
+
+        state.enter_digit(clue, value % 9 + 1);
+        state.clear_cell(clue);
+        assert_eq!(state.sudoku[clue], value);
+        assert!(state.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn switching_board_size_resizes_state() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.set_board_size(2);
+        assert_eq!(state.geometry.side(), 4);
+        assert_eq!(state.sudoku.len(), 16);
+        assert_eq!(state.highlight.len(), 16);
+        assert_eq!(state.remaining_digits().len(), 4);
+
+        // Moving past the edge of the smaller board keeps the selection on it.
+        state.select_cell(15);
+        state.handle_key(KeyInput::Down);
+        state.handle_key(KeyInput::Right);
+        assert_eq!(state.selected_cell, Some(15));
+
+        // Digits beyond the board size are ignored.
+        let empty = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        state.select_cell(empty);
+        state.handle_key(KeyInput::Digit(5));
+        assert_eq!(state.sudoku[empty], 0);
+    }
+
+    #[test]
+    fn help_opens_with_f1_and_closes_with_escape() {
+        assert_eq!(key_input(&Key::Named(NamedKey::F1)), Some(KeyInput::Help));
+        assert_eq!(
+            key_input(&Key::Named(NamedKey::Escape)),
+            Some(KeyInput::Escape)
+        );
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.handle_key(KeyInput::Help);
+        state.handle_key(KeyInput::Help);
+        assert!(state.help_open);
+
+        // The board ignores keys while the help covers it.
+        let empty = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        state.select_cell(empty);
+        state.handle_key(KeyInput::Digit(state.solved[empty]));
+        assert_eq!(state.sudoku[empty], 0);
+
+        state.handle_key(KeyInput::Escape);
+        assert!(!state.help_open);
+        state.history_open = true;
+        state.handle_key(KeyInput::Escape);
+        assert!(!state.history_open);
+    }
+
+    #[test]
+    fn keys_enter_clear_and_move_the_selection() {
+        let decode = |c: &str| key_input(&Key::Character(c.into()));
+        assert_eq!(decode("7"), Some(KeyInput::Digit(7)));
+        assert_eq!(decode("0"), Some(KeyInput::Clear));
+        assert_eq!(decode("x"), None);
+        for (key, input) in [
+            (NamedKey::Delete, KeyInput::Clear),
+            (NamedKey::Backspace, KeyInput::Clear),
+            (NamedKey::ArrowLeft, KeyInput::Left),
+        ] {
+            assert_eq!(key_input(&Key::Named(key)), Some(input));
+        }
+
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        state.select_cell(index);
+        state.handle_key(KeyInput::Digit(state.solved[index]));
+        assert_eq!(state.sudoku[index], state.solved[index]);
+        state.handle_key(KeyInput::Clear);
+        assert_eq!(state.sudoku[index], 0);
+
+        // The arrows stop at the edges instead of wrapping around.
+        state.select_cell(0);
+        state.handle_key(KeyInput::Up);
+        state.handle_key(KeyInput::Left);
+        assert_eq!(state.selected_cell, Some(0));
+        state.handle_key(KeyInput::Right);
+        state.handle_key(KeyInput::Down);
+        assert_eq!(state.selected_cell, Some(10));
+        assert!(state.highlight[9] && state.highlight[19] && !state.highlight[40]);
+        let last = state.geometry.cell_count() - 1;
+        state.select_cell(last);
+        state.handle_key(KeyInput::Right);
+        assert_eq!(state.selected_cell, Some(last));
+
+        // Clues can be moved onto, but not changed.
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        let (from, key) = if state.geometry.col_of(clue) > 0 {
+            (clue - 1, KeyInput::Right)
+        } else {
+            (clue + 1, KeyInput::Left)
+        };
+        state.select_cell(from);
+        state.handle_key(key);
+        assert_eq!(state.selected_cell, Some(clue));
+        let value = state.sudoku[clue];
+        state.handle_key(KeyInput::Clear);
+        assert_eq!(state.sudoku[clue], value);
+    }
+
+    #[test]
+    fn timer_ticks_on_second_boundaries() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.active_time = Duration::from_millis(2300);
+        state.last_tick = Instant::now();
+        let delay = state.until_next_tick();
+        assert!(delay <= Duration::from_millis(700) && delay > Duration::from_millis(600));
+
+        // An expiring hint highlight needs an earlier refresh.
+        state.hint = Some((0, Instant::now() - Duration::from_millis(800)));
+        assert!(state.until_next_tick() <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn countdown_ends_the_game_when_time_is_up() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.cycle_timer_mode();
+        assert_eq!(state.timer_mode, Timer::CountDown(Duration::from_secs(300)));
+        state.start_clock();
+        state.active_time = Duration::from_secs(299);
+        state.last_tick = Instant::now();
+        state.check_countdown();
+        assert!(!state.game_over);
+        assert_eq!(state.clock_seconds(), 1);
+
+        state.active_time = Duration::from_secs(300);
+        state.check_countdown();
+        assert!(state.game_over && state.time_up);
+        assert_eq!((state.clock_seconds(), state.elapsed_seconds()), (0, 300));
+
+        // The challenge carries over to the next game, which starts afresh.
+        state.new_game();
+        assert_eq!(state.timer_mode, Timer::CountDown(Duration::from_secs(300)));
+        assert!(!state.game_over && !state.time_up);
+    }
+
+    #[test]
+    fn placements_flash_and_fade() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let mut empty = (0..state.sudoku.len()).filter(|&i| state.sudoku[i] == 0);
+        let (first, second) = (empty.next().unwrap(), empty.next().unwrap());
+
+        state.apply_guess(first, state.solved[first]);
+        let (correct, level) = state.flash_level(first).unwrap();
+        assert!(correct && level > 0.5);
+        assert_eq!(state.flash_level(second), None);
+
+        state.apply_guess(second, state.solved[second] % 9 + 1);
+        assert_eq!(state.flash_level(first), None);
+        assert!(!state.flash_level(second).unwrap().0);
+
+        state.expire_flash();
+        assert!(state.flash.is_some());
+        let faded = Instant::now() - Duration::from_millis(FLASH_MS);
+        state.flash = Some((second, false, faded));
+        assert_eq!(state.flash_level(second), None);
+        state.expire_flash();
+        assert!(state.flash.is_none());
+    }
+
+    #[test]
+    fn completed_units_glow_and_fade() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        let row = state.geometry.row_start(index);
+        let side = state.geometry.side();
+        for i in row..row + side {
+            if i != index {
+                state.sudoku[i] = state.solved[i];
+            }
+        }
+        let outside = (0..state.sudoku.len())
+            .find(|&i| !state.unit_cells(index).contains(&i))
+            .unwrap();
+
+        state.apply_guess(index, state.solved[index]);
+        assert!(state.completion_level(row).is_some_and(|level| level > 0.5));
+        assert!(state.completion_level(row + side - 1).is_some());
+        assert_eq!(state.completion_level(outside), None);
+
+        let faded = Instant::now() - Duration::from_millis(COMPLETION_MS);
+        for (_, at) in &mut state.completed {
+            *at = faded;
+        }
+        assert_eq!(state.completion_level(row), None);
+        state.expire_completed();
+        assert!(state.completed.is_empty());
+    }
+
+    #[test]
+    fn solving_opens_the_completion_dialog_once() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let empty: Vec<usize> = (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0)
+            .collect();
+        for &i in &empty {
+            state.enter_digit(i, state.solved[i]);
+        }
+        assert!(state.solved_dialog_open);
+
+        state.solved_dialog_open = false;
+        state.recompute_voids_and_maybe_stop_timer();
+        assert!(!state.solved_dialog_open);
+
+        // Giving up doesn't count as solving.
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.reveal_solution();
+        assert!(!state.solved_dialog_open);
+
+        // Finishing by hand after a hint does.
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.give_hint();
+        for i in 0..state.sudoku.len() {
+            if state.sudoku[i] == 0 {
+                state.enter_digit(i, state.solved[i]);
+            }
+        }
+        assert!(state.revealed.contains(&true));
+        assert!(state.solved_dialog_open);
+    }
+
+    #[test]
+    fn full_but_wrong_board_is_not_solved() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.is_clue.iter().position(|&c| !c).unwrap();
+        state.sudoku = state.solved.clone();
+        state.sudoku[index] = state.solved[index] % 9 + 1;
+        state.recompute_voids_and_maybe_stop_timer();
+
+        assert_eq!(state.voids, 0);
+        assert!(!state.is_solved());
+        assert!(!state.solved_dialog_open);
+        assert_eq!(state.stopped_time, None);
+        assert!(state.stats.best_time(state.level).is_none());
+
+        state.sudoku[index] = state.solved[index];
+        state.recompute_voids_and_maybe_stop_timer();
+        assert!(state.is_solved() && state.solved_dialog_open);
+    }
+
+    #[test]
+    fn full_board_with_conflicts_keeps_the_timer_running() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        // Two swapped digits in a row break the columns, even when the stored
+        // solution agrees, as a damaged save file could.
+        state.solved.swap(0, 1);
+        state.sudoku = state.solved.clone();
+        state.recompute_voids_and_maybe_stop_timer();
+
+        assert_eq!(state.voids, 0);
+        assert!(!state.is_solved());
+        assert_eq!(state.stopped_time, None);
+        assert!(!state.solved_dialog_open);
+    }
+
+    #[test]
+    fn new_game_asks_before_discarding_progress() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let puzzle = state.sudoku.clone();
+        state.request_new_game();
+        assert!(!state.confirm_new_game);
+        assert_ne!(state.sudoku, puzzle, "an untouched board is replaced right away");
+
+        let empty = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        state.enter_digit(empty, state.solved[empty]);
+        let puzzle = state.sudoku.clone();
+        state.request_new_game();
+        assert!(state.confirm_new_game);
+        assert_eq!(state.sudoku, puzzle);
+        state.confirm_new_game = false;
+        state.request_daily();
+        assert!(state.confirm_new_game && state.daily_requested);
+        state.accept_new_game();
+        assert_eq!(state.daily, Some(today()));
+
+        // Once solved, there is nothing left to lose.
+        state.confirm_new_game = false;
+        state.reveal_solution();
+        state.request_new_game();
+        assert!(!state.confirm_new_game);
+    }
+
+    #[test]
+    fn auto_notes_mark_current_candidates() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.auto_notes();
+        for index in 0..state.sudoku.len() {
+            for digit in 1..=9 {
+                let noted = state.notes[index][digit as usize - 1];
+                let expected = state.sudoku[index] == 0 && state.can_place(index, digit);
+                assert_eq!(noted, expected);
+            }
+        }
+
+        // Entering a digit rules it out of the peers' notes on the next run.
+        let empty = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        let digit = state.solved[empty];
+        state.apply_guess(empty, digit);
+        state.auto_notes();
+        let row = state.geometry.row_start(empty);
+        assert!((row..row + 9).all(|i| !state.notes[i][digit as usize - 1]));
+    }
+
+    #[test]
+    fn placing_a_digit_strikes_it_from_peer_notes() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.auto_notes();
+        // An empty cell whose digit is still noted in one of its peers.
+        let index = (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0)
+            .find(|&i| {
+                let d = state.solved[i] as usize - 1;
+                state.unit_cells(i).iter().any(|&p| p != i && state.notes[p][d])
+            })
+            .unwrap();
+        let digit = state.solved[index];
+        state.center_notes[index][digit as usize - 1] = true;
+        let before = state.notes.clone();
+        let center_before = state.center_notes[index];
+
+        state.apply_guess(index, digit);
+        let peers = state.unit_cells(index);
+        assert!(peers.iter().all(|&i| !state.notes[i][digit as usize - 1]));
+
+        // Undo puts the struck candidates back, the cell's own notes included;
+        // redo strikes them again.
+        state.undo();
+        let restored: Vec<_> = peers.iter().filter(|&&i| i != index).collect();
+        assert!(restored.iter().all(|&&i| state.notes[i] == before[i]));
+        assert_eq!(state.notes[index], before[index]);
+        assert_eq!(state.center_notes[index], center_before);
+        state.redo();
+        assert!(peers.iter().all(|&i| !state.notes[i][digit as usize - 1]));
+        assert!(!state.notes[index].contains(&true) && !state.center_notes[index].contains(&true));
+
+        // Switched off, peers keep their notes.
+        state.undo();
+        state.settings.auto_strike = false;
+        state.apply_guess(index, digit);
+        assert!(restored.iter().all(|&&i| state.notes[i] == before[i]));
+    }
+
+    /// A classic 9×9 state without any clues, to write test positions into.
+    fn empty_state() -> AppState {
+        AppState::from_sudoku(
+            sudoku::Sudoku(vec![0; 81], vec![0; 81], Variant::Classic),
+            DEFAULT_DIFFICULTY,
+        )
+    }
+
+    #[test]
+    fn conflicts_are_found_in_row_column_and_block() {
+        // Partners of the top-left cell sharing only its row, only its column
+        // and only its block.
+        for partner in [8, 72, 10] {
+            let mut state = empty_state();
+            state.sudoku[0] = 5;
+            state.sudoku[partner] = 5;
+            assert!(state.has_conflict(0), "partner {partner}");
+            assert!(state.has_conflict(partner), "partner {partner}");
+        }
+
+        // Nothing shared, or different digits: no conflict.
+        let mut state = empty_state();
+        state.sudoku[0] = 5;
+        state.sudoku[40] = 5;
+        state.sudoku[8] = 6;
+        assert!((0..81).all(|i| !state.has_conflict(i)));
+        // Empty cells never conflict.
+        state.sudoku[1] = 0;
+        assert!(!state.has_conflict(1));
+
+        // The center is on the main diagonal with the top-left cell.
+        state.variant = Variant::Diagonal;
+        assert!(state.has_conflict(0) && state.has_conflict(40));
+    }
+
+    #[test]
+    fn guess_repeating_a_clue_is_a_conflict() {
+        let line =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut state =
+            AppState::from_sudoku(sudoku::Sudoku::from_str_line(line).unwrap(), DEFAULT_DIFFICULTY);
+        assert!(state.is_clue[0] && !state.is_clue[2]);
+        state.apply_guess(2, 5);
+        assert!(state.has_conflict(2) && state.has_conflict(0));
+        // Only the guess is marked; the clue stays as it is.
+        let conflicts = state.conflicts();
+        assert!(conflicts[2] && !conflicts[0]);
+    }
+
+    #[test]
+    fn difficulty_preset_starts_a_game_at_its_level() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let (_, level) = DIFFICULTY_PRESETS[3];
+        state.start_preset(level);
+        assert_eq!((state.difficulty as u8, state.level), (level, level));
+        assert!(!state.confirm_new_game);
+        assert!(state.voids > 0);
+
+        // With progress to lose, declining keeps the game and its difficulty.
+        let empty = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        state.enter_digit(empty, state.solved[empty]);
+        let (_, other) = DIFFICULTY_PRESETS[1];
+        state.start_preset(other);
+        assert!(state.confirm_new_game);
+        assert_eq!(state.difficulty as u8, level);
+        state.decline_new_game();
+        assert_eq!((state.difficulty as u8, state.level), (level, level));
+
+        state.start_preset(other);
+        state.accept_new_game();
+        assert_eq!((state.difficulty as u8, state.level), (other, other));
+        assert!(!state.confirm_new_game);
+    }
+
+    #[test]
+    fn difficulty_slider_waits_for_the_next_game() {
+        let mut state = AppState::new(3.0);
+        state.difficulty = 6.0;
+        assert_eq!(state.level, 3);
+        state.restart();
+        assert_eq!(state.level, 3);
+        state.stopped_time = Some(100);
+        assert_eq!(state.score(), SCORE_BASE + 3 * SCORE_PER_LEVEL - 100 * SCORE_PER_SECOND);
+
+        state.new_game();
+        assert_eq!((state.difficulty, state.level), (6.0, 6));
+    }
+
+    #[test]
+    fn clue_target_sets_the_clue_count_of_classic_puzzles() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.cycle_clue_target();
+        assert_eq!(state.settings.clue_target, Some(32));
+        state.new_game();
+        assert_eq!(state.is_clue.iter().filter(|&&c| c).count(), 32);
+        assert!(state.board_snapshot().is_unique());
+
+        // Other board sizes keep following the difficulty level.
+        state.set_board_size(2);
+        assert_eq!(state.sudoku.len(), 16);
+    }
+
+    #[test]
+    fn failed_generation_keeps_the_game_and_says_why() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let board = state.sudoku.clone();
+        // A hand-edited clue target too low for any unique puzzle.
+        state.settings.clue_target = Some(5);
+        state.new_game();
+        assert_eq!(state.sudoku, board);
+        assert!(state.generation_error.is_some());
+        assert_eq!(state.import_error, None);
+
+        state.settings.clue_target = None;
+        state.new_game();
+        assert_eq!(state.generation_error, None);
+
+        // The first game falls back to a plain classic puzzle.
+        let settings = Settings {
+            clue_target: Some(5),
+            ..Settings::default()
+        };
+        let state = AppState::with_settings(DEFAULT_DIFFICULTY, settings);
+        assert_eq!(state.generation_error, None);
+        assert!(state.board_snapshot().is_unique());
+    }
+
+    #[test]
+    fn difficulty_is_rounded_and_clamped() {
+        let max = sudoku::MAX_DIFFICULTY_LEVEL;
+        let state = AppState::new(max as f64 + 5.0);
+        assert_eq!((state.difficulty, state.level), (max as f64, max));
+        let state = AppState::new(-2.0);
+        assert_eq!((state.difficulty, state.level), (0.0, 0));
+
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.difficulty = 2.6;
+        state.new_game();
+        assert_eq!((state.difficulty, state.level), (3.0, 3));
+    }
+
+    #[test]
+    fn accuracy_counts_correct_placements() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        assert_eq!(state.accuracy(), None);
+
+        let index = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        let right = state.solved[index];
+        state.apply_guess(index, right % 9 + 1);
+        state.apply_guess(index, right);
+        assert_eq!((state.guesses, state.correct_guesses), (2, 1));
+        assert_eq!(state.accuracy(), Some(50.0));
 
-    let color = if state.is_clue[index] {
-        CLUE_TEXT_COLOR
-    } else if value != 0 && state.selected_cell == Some(index) && state.collision {
-        FAIL_TEXT_COLOR
-    } else {
-        GUESS_TEXT_COLOR
-    };
+        state.new_game();
+        assert_eq!((state.guesses, state.correct_guesses), (0, 0));
+    }
 
-    let background = if state.selected_cell == Some(index) {
-        SELECTED_BACKGROUND_COLOR
-    } else if state.highlight[index] {
-        SUDOKU_HIGHLIGHT_COLOR
-    } else {
-        SUDOKU_BACKGROUND_COLOR
-    };
+    #[test]
+    fn candidate_counts_follow_the_board() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        assert_eq!(state.candidate_count(clue), 0);
 
-    let cell_label = label(text)
-        .text_alignment(TextAlign::Center)
-        .text_size(24.0)
-        .color(color);
+        // An empty cell with an empty peer; writing one of its candidates into
+        // the peer takes that candidate away.
+        let (index, peer) = (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0)
+            .find_map(|i| {
+                let unit = state.unit_cells(i);
+                let peer = unit.into_iter().find(|&p| p != i && state.sudoku[p] == 0)?;
+                Some((i, peer))
+            })
+            .unwrap();
+        let before = state.candidate_count(index);
+        let digit = (1..=9).find(|&d| state.can_place(index, d)).unwrap();
+        state.sudoku[peer] = digit;
+        assert_eq!(state.candidate_count(index), before - 1);
+    }
 
-    button(cell_label, move |state: &mut AppState| {
+    #[test]
+    fn candidates_are_the_digits_missing_around_a_cell() {
+        let line =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let state = AppState::from_sudoku(
+            sudoku::Sudoku::from_str_line(line).unwrap(),
+            DEFAULT_DIFFICULTY,
+        );
+        let mask = |digits: &[i8]| digits.iter().fold(0, |mask, &d| mask | digit_bit(d));
+        assert_eq!(state.candidates(2), mask(&[1, 2, 4]));
+        assert_eq!(state.candidates(30), mask(&[5, 7, 9]));
+        // The center cell is a naked single.
+        assert_eq!(state.candidates(40), mask(&[5]));
+        assert_eq!(state.candidate_count(40), 1);
+        assert_eq!(state.candidates(0), 0, "filled cells have none");
+
+        assert_eq!(empty_state().candidates(0), 0x1ff);
+        let mut diagonal = empty_state();
+        diagonal.variant = Variant::Diagonal;
+        diagonal.sudoku[80] = 9;
+        assert_eq!(diagonal.candidates(0), 0xff);
+        assert_eq!(diagonal.candidates(2), 0x1ff, "off the diagonals");
+    }
+
+    #[test]
+    fn rejected_import_reports_why_and_keeps_the_game() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let puzzle = state.board_snapshot().to_str_line();
+        let board = state.sudoku.clone();
+
+        state.import_text = "12x".to_string();
+        state.import();
+        assert_eq!(state.sudoku, board);
+        assert!(state.import_error.as_deref().is_some_and(|err| err.starts_with("Import failed: ")));
+
+        state.import_text = puzzle;
+        state.import();
+        assert_eq!(state.import_error, None);
+    }
+
+    #[test]
+    fn replacing_the_board_resets_interaction_state() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let (index, wrong) = wrong_digit(&state, true);
         state.select_cell(index);
-    })
-    .padding(0.0)
-    .background_color(background)
-    .corner_radius(0.0)
-    .border_color(Color::TRANSPARENT)
-}
+        state.apply_guess(index, wrong);
+        assert_eq!((state.fail, state.collision), (Some(index), true));
 
-fn info_bar(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
-    let elapsed = state.elapsed_seconds();
-    let minutes = elapsed / 60;
-    let seconds = elapsed % 60;
+        // A 4×4 board, on which the old selection doesn't even exist.
+        let small =
+            sudoku::Sudoku::new(sudoku::SUGGESTED_DIFFICULTY_LEVEL, 2, Variant::Classic).unwrap();
+        state.import_text = small.to_str_line();
+        state.import();
+        assert_eq!(state.sudoku.len(), 16);
+        assert_eq!((state.selected_cell, state.fail, state.collision), (None, None, false));
+        assert!(state.highlight.len() == 16 && !state.highlight.contains(&true));
+        assert!(!state.highlight_same.contains(&true));
+    }
 
-    flex_row((
-    FlexSpacer::Fixed(DEFAULT_GAP),
-        label(format!("Time: {minutes}:{seconds:02}")).font(FontStack::Source("monospace".into())),
-        FlexSpacer::Flex(1.0),
-        label(format!("Voids left: {}", state.voids)),
-                FlexSpacer::Flex(1.0),
-        label(format!("Fails: {}", state.fails)),
-                FlexSpacer::Flex(1.0),
-        label(format!("Difficulty: {:.0}", state.difficulty)),
-                //FlexSpacer::Flex(1.0),
-        //sized_box(
-            slider(
-                0.0,
-                sudoku::MAX_DIFFICULTY_LEVEL as f64,
-                state.difficulty,
-                |state: &mut AppState, val| {
-                    state.difficulty = val;
-                },
-            )
-            .step(1.0)
-            .width(80.px()),
-        //)
-        //.width(40_i32.px()),
-                FlexSpacer::Flex(1.0),
-        text_button("New Game", |state: &mut AppState| state.new_game()).padding(8.0),
-        FlexSpacer::Fixed(DEFAULT_GAP),
-    ))
-}
+    #[cfg(feature = "solution-explorer")]
+    #[test]
+    fn explorer_steps_through_solutions() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.open_explorer();
+        assert_eq!(state.explorer.as_ref().unwrap().solutions, vec![state.solved.clone()]);
 
-/// Build the full Sudoku board (3×3 blocks of 3×3 cells).
-fn build_board(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
-    let mut sudoku_blocks = Vec::with_capacity(BOARD_BLOCKS * BOARD_BLOCKS);
+        // Without clues the search stops at the limit.
+        state.sudoku.fill(0);
+        state.open_explorer();
+        state.step_explorer(-1);
+        let explorer = state.explorer.as_ref().unwrap();
+        assert_eq!(explorer.solutions.len(), EXPLORER_LIMIT);
+        assert_eq!(explorer.shown, EXPLORER_LIMIT - 1);
 
-    for block_row in 0..BOARD_BLOCKS {
-        for block_col in 0..BOARD_BLOCKS {
-            let mut block_cells = Vec::with_capacity(BLOCK_SIDE * BLOCK_SIDE);
+        state.handle_key(KeyInput::Escape);
+        assert!(state.explorer.is_none());
+    }
 
-            for cell_row in 0..BLOCK_SIDE {
-                for cell_col in 0..BLOCK_SIDE {
-                    let index = block_row * SIDE * BLOCK_SIDE
-                        + cell_row * SIDE
-                        + block_col * BLOCK_SIDE
-                        + cell_col;
+    #[test]
+    fn pen_digit_is_placed_in_every_tapped_cell() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.toggle_pen_mode();
+        let digit = state.solved[state.sudoku.iter().position(|&n| n == 0).unwrap()];
+        let targets: Vec<usize> = (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0 && state.solved[i] == digit)
+            .collect();
 
-                    block_cells.push(cell(state, index).grid_pos(cell_col as i32, cell_row as i32));
-                }
+        state.press_pad_digit(digit);
+        assert_eq!(state.active_digit, Some(digit));
+        for i in 0..state.sudoku.len() {
+            assert_eq!(state.highlight_same[i], state.sudoku[i] == digit);
+            assert_eq!(state.highlight_fits[i], state.sudoku[i] == 0 && state.can_place(i, digit));
+        }
+
+        for &i in &targets {
+            state.tap_cell(i);
+        }
+        assert!(targets.iter().all(|&i| state.sudoku[i] == digit));
+        assert!(!state.highlight_fits.contains(&true));
+        assert_eq!(state.active_digit, Some(digit));
+
+        state.press_pad_digit(digit);
+        assert_eq!(state.active_digit, None);
+        state.toggle_pen_mode();
+        assert!(!state.pen_mode);
+    }
+
+    #[test]
+    fn auto_advance_wraps_and_stops_on_a_full_board() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.settings.auto_advance = true;
+        let empty: Vec<usize> = (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0)
+            .collect();
+        let (first, last) = (empty[0], *empty.last().unwrap());
+
+        // The last empty cell moves on to the first one, past the board's end.
+        state.select_cell(last);
+        state.enter_digit(last, state.solved[last]);
+        assert_eq!(state.selected_cell, Some(first));
+
+        // A wrong guess stays put for correction.
+        let wrong = state.solved[first] % state.geometry.side() as i8 + 1;
+        state.enter_digit(first, wrong);
+        assert_eq!(state.selected_cell, Some(first));
+
+        for &i in &empty[1..] {
+            state.sudoku[i] = state.solved[i];
+        }
+        state.select_cell(first);
+        state.enter_digit(first, state.solved[first]);
+        assert_eq!(state.selected_cell, Some(first));
+    }
+
+    #[test]
+    fn pad_digit_dropped_on_a_cell_lands_there() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let mut empty = (0..state.sudoku.len()).filter(|&i| state.sudoku[i] == 0);
+        let (first, second) = (empty.next().unwrap(), empty.next().unwrap());
+        state.select_cell(first);
+
+        // Dragged onto another cell, the digit skips the selected one.
+        let digit = state.solved[second];
+        state.grab_pad_digit(digit);
+        assert_eq!(state.dragged, Some(digit));
+        state.release_cell(second, false, Instant::now());
+        state.release_pointer();
+        assert_eq!(state.sudoku[second], digit);
+        assert_eq!(state.sudoku[first], 0);
+        assert_eq!(state.selected_cell, Some(second));
+        assert_eq!(state.dragged, None);
+
+        // Released on the pad digit itself, it is a plain press.
+        state.select_cell(first);
+        let digit = state.solved[first];
+        state.grab_pad_digit(digit);
+        state.release_pad_digit(digit);
+        state.release_pointer();
+        assert_eq!(state.sudoku[first], digit);
+
+        // Dropped off the board, it goes nowhere.
+        state.grab_pad_digit(digit);
+        state.release_pointer();
+        assert_eq!(state.dragged, None);
+        state.release_pad_digit(digit);
+        assert_eq!(state.sudoku[first], digit);
+    }
+
+    /// Press cell `index` and release it `held` later.
+    fn press(state: &mut AppState, index: usize, held: u64) {
+        let now = Instant::now();
+        state.press_cell(index, now);
+        state.release_cell(index, false, now + Duration::from_millis(held));
+    }
+
+    #[test]
+    fn long_press_opens_the_digit_picker() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&n| n == 0).unwrap();
+
+        // Short presses select, on the selected cell too.
+        press(&mut state, index, LONG_PRESS_MS / 5);
+        press(&mut state, index, LONG_PRESS_MS / 5);
+        assert_eq!(state.selected_cell, Some(index));
+        assert_eq!(state.picker, None);
+        press(&mut state, index, LONG_PRESS_MS);
+        assert_eq!(state.picker, Some(index));
+        state.pick_digit(state.solved[index]);
+        assert_eq!(state.sudoku[index], state.solved[index]);
+        assert_eq!(state.picker, None);
+
+        // In note mode the picker stays open for more notes.
+        let other = state.sudoku.iter().position(|&n| n == 0).unwrap();
+        state.note_mode = NoteMode::Corner;
+        press(&mut state, other, LONG_PRESS_MS);
+        state.pick_digit(1);
+        state.pick_digit(2);
+        assert!(state.notes[other][0] && state.notes[other][1]);
+        assert_eq!(state.picker, Some(other));
+
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        press(&mut state, clue, LONG_PRESS_MS);
+        assert_eq!(state.picker, Some(other));
+
+        // A press that ends over another cell is no tap on either.
+        state.picker = None;
+        let now = Instant::now();
+        state.press_cell(index, now);
+        state.release_cell(other, false, now);
+        assert_eq!(state.selected_cell, Some(clue));
+    }
+
+    #[test]
+    fn double_click_reveals_a_cell_as_a_hint() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let mut empty = (0..state.sudoku.len()).filter(|&i| state.sudoku[i] == 0);
+        let (first, second) = (empty.next().unwrap(), empty.next().unwrap());
+        let now = Instant::now();
+
+        state.click_cell(first, now);
+        state.click_cell(first, now + Duration::from_millis(DOUBLE_CLICK_MS / 2));
+        assert_eq!(state.confirm_reveal, Some(first));
+        assert_eq!(state.sudoku[first], 0);
+        state.accept_reveal();
+        assert_eq!(state.confirm_reveal, None);
+        assert_eq!(state.sudoku[first], state.solved[first]);
+        assert!(state.revealed[first]);
+        assert!(state.assisted && state.hints_used == 1);
+        assert_eq!(state.picker, None);
+
+        // Clicks too far apart are two taps.
+        state.click_cell(second, now);
+        state.click_cell(second, now + Duration::from_millis(DOUBLE_CLICK_MS * 2));
+        assert_eq!(state.sudoku[second], 0);
+        assert_eq!(state.hints_used, 1);
+
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        state.click_cell(clue, now);
+        state.click_cell(clue, now);
+        assert_eq!(state.confirm_reveal, None);
+        assert_eq!(state.hints_used, 1);
+    }
+
+    #[test]
+    fn double_tap_on_the_selected_cell_neither_picks_nor_reveals() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&n| n == 0).unwrap();
+        state.select_cell(index);
+
+        press(&mut state, index, 0);
+        press(&mut state, index, 0);
+        assert_eq!(state.picker, None);
+        assert_eq!(state.sudoku[index], 0);
+        assert!(!state.assisted && state.hints_used == 0);
+
+        // Declining the prompt costs nothing.
+        assert_eq!(state.confirm_reveal, Some(index));
+        state.handle_key(KeyInput::Escape);
+        assert_eq!(state.confirm_reveal, None);
+        assert!(!state.revealed[index] && state.hints_used == 0);
+    }
+
+    #[test]
+    fn corner_and_center_notes_are_kept_apart() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&n| n == 0).unwrap();
+
+        state.cycle_note_mode();
+        assert_eq!(state.note_mode, NoteMode::Corner);
+        state.enter_digit(index, 1);
+        state.cycle_note_mode();
+        assert_eq!(state.note_mode, NoteMode::Center);
+        state.enter_digit(index, 2);
+        assert_eq!((state.notes[index][0], state.notes[index][1]), (true, false));
+        assert_eq!((state.center_notes[index][0], state.center_notes[index][1]), (false, true));
+        assert_eq!(state.sudoku[index], 0);
+
+        state.cycle_note_mode();
+        assert_eq!(state.note_mode, NoteMode::Off);
+        state.enter_digit(index, state.solved[index]);
+        assert!(!state.notes[index].contains(&true));
+        assert!(!state.center_notes[index].contains(&true));
+    }
+
+    #[test]
+    fn right_click_selection_enters_notes() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let mut empty = (0..state.sudoku.len()).filter(|&i| state.sudoku[i] == 0);
+        let (first, second) = (empty.next().unwrap(), empty.next().unwrap());
+
+        state.select_cell_for_notes(first);
+        state.enter_digit(first, 4);
+        state.enter_digit(first, 5);
+        assert_eq!(state.sudoku[first], 0);
+        assert!(state.notes[first][3] && state.notes[first][4]);
+
+        state.select_cell(second);
+        state.enter_digit(second, state.solved[second]);
+        assert_eq!(state.sudoku[second], state.solved[second]);
+    }
+
+    #[test]
+    fn repeating_a_digit_clears_the_cell() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&n| n == 0).unwrap();
+        let voids = state.voids;
+        state.select_cell(index);
+
+        state.apply_guess(index, state.solved[index]);
+        state.apply_guess(index, state.solved[index]);
+        assert_eq!((state.sudoku[index], state.voids), (0, voids));
+        state.undo();
+        assert_eq!(state.sudoku[index], state.solved[index]);
+
+        // A failed guess goes with its fail marks; the fail count stays.
+        let wrong = (1..=9)
+            .find(|&d| d != state.solved[index] && !state.can_place(index, d))
+            .unwrap_or(state.solved[index] % 9 + 1);
+        state.apply_guess(index, wrong);
+        let fails = state.fails;
+        state.apply_guess(index, wrong);
+        assert_eq!(state.sudoku[index], 0);
+        assert_eq!((state.fail, state.collision, state.fails), (None, false, fails));
+
+        // Clues stay put.
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        state.apply_guess(clue, state.sudoku[clue]);
+        assert_eq!(state.sudoku[clue], state.solved[clue]);
+    }
+
+    #[test]
+    fn reselecting_a_failed_cell_leaves_it_clean() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        // An empty cell and a wrong digit that conflicts with a peer.
+        let (index, wrong) = wrong_digit(&state, true);
+        let right = (0..state.sudoku.len())
+            .find(|&i| i != index && state.sudoku[i] == 0)
+            .unwrap();
+        state.apply_guess(right, state.solved[right]);
+
+        state.select_cell(index);
+        state.apply_guess(index, wrong);
+        assert_eq!((state.fail, state.collision), (Some(index), true));
+
+        state.select_cell(index);
+        assert_eq!(state.sudoku[index], 0);
+        assert_eq!((state.fail, state.collision), (None, false));
+        assert_eq!(state.selected_cell, Some(index));
+        assert!(!state.highlight_same.contains(&true));
+
+        // Nor does the failure carry over to another guess selected next.
+        state.apply_guess(index, wrong);
+        state.select_cell(right);
+        assert!(!state.collision);
+    }
+
+    #[test]
+    fn failed_guess_can_outlast_the_selection() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.settings.auto_clear_fail = false;
+        // An empty cell and a wrong digit that conflicts with a peer.
+        let (index, wrong) = wrong_digit(&state, true);
+        let other = (0..state.sudoku.len())
+            .find(|&i| i != index && state.sudoku[i] == 0)
+            .unwrap();
+
+        state.select_cell(index);
+        state.apply_guess(index, wrong);
+        state.select_cell(other);
+        assert_eq!(state.sudoku[index], wrong);
+        assert!(state.conflicts()[index]);
+        assert_eq!((state.fail, state.collision), (None, false));
+
+        // Clearing it stays up to the player.
+        state.clear_cell(index);
+        assert_eq!(state.sudoku[index], 0);
+    }
+
+    #[test]
+    fn overwriting_a_wrong_guess_keeps_counts_right() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        // An empty cell with two wrong digits that already occur among its peers.
+        let (index, wrong) = (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0)
+            .find_map(|i| {
+                let wrong: Vec<i8> = (1..=9)
+                    .filter(|&d| d != state.solved[i] && !state.can_place(i, d))
+                    .collect();
+                (wrong.len() >= 2).then_some((i, wrong))
+            })
+            .unwrap();
+        let voids = state.voids;
+        state.select_cell(index);
+
+        state.apply_guess(index, wrong[0]);
+        assert_eq!((state.voids, state.fails, state.fail), (voids - 1, 1, Some(index)));
+
+        state.apply_guess(index, wrong[1]);
+        assert_eq!((state.voids, state.fails, state.fail), (voids - 1, 2, Some(index)));
+
+        state.apply_guess(index, state.solved[index]);
+        assert_eq!((state.voids, state.fails, state.fail), (voids - 1, 2, None));
+        assert!(!state.collision);
+    }
+
+    #[test]
+    fn strict_mode_turns_down_wrong_digits() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.settings.strict = true;
+        let index = state.sudoku.iter().position(|&n| n == 0).unwrap();
+        let wrong = state.solved[index] % 9 + 1;
+        let voids = state.voids;
+
+        state.apply_guess(index, wrong);
+        assert_eq!(state.sudoku[index], 0);
+        assert_eq!((state.voids, state.fails), (voids, 1));
+        assert_eq!(state.rejected, Some((index, wrong)));
+        assert!(state.undo_stack.is_empty());
+
+        state.apply_guess(index, state.solved[index]);
+        assert_eq!(state.sudoku[index], state.solved[index]);
+        assert_eq!((state.voids, state.fails, state.rejected), (voids - 1, 1, None));
+    }
+
+    #[test]
+    fn logic_only_puzzles_need_no_guessing() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.difficulty = sudoku::MAX_DIFFICULTY_LEVEL as f64;
+        state.settings.logic_only = true;
+        state.settings.minimal = true;
+        for _ in 0..3 {
+            state.new_game();
+            assert!(state.board_snapshot().solvable_by_logic());
+        }
+        assert!(state.settings.logic_only);
+    }
+
+    #[test]
+    fn retyping_a_digit_clears_it_or_does_nothing() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        let digit = state.solved[index];
+
+        state.apply_guess(index, digit);
+        state.apply_guess(index, digit);
+        assert_eq!(state.sudoku[index], 0);
+        assert_eq!((state.guesses, state.undo_stack.len()), (1, 2));
+
+        state.settings.retype_clears = false;
+        state.apply_guess(index, digit);
+        // A failed guess elsewhere stays flagged through the retyped digit.
+        let (other, wrong) = wrong_digit(&state, true);
+        state.apply_guess(other, wrong);
+        let (guesses, edits, unsaved) = (state.guesses, state.undo_stack.len(), state.unsaved);
+        state.unsaved = false;
+        state.apply_guess(index, digit);
+        assert_eq!(state.sudoku[index], digit);
+        assert_eq!((state.guesses, state.undo_stack.len()), (guesses, edits));
+        assert_eq!((state.fail, state.collision), (Some(other), true));
+        assert!(!state.unsaved && unsaved, "nothing was recomputed");
+    }
+
+    #[test]
+    fn instant_wrong_flags_mistakes_without_a_conflict() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let (index, wrong) = wrong_digit(&state, false);
+
+        // By default a wrong digit that repeats nothing goes unnoticed.
+        state.apply_guess(index, wrong);
+        assert_eq!((state.fails, state.fail), (0, None));
+        assert!(!state.shows_wrong(index));
+        state.clear_cell(index);
+
+        state.settings.instant_wrong = true;
+        state.select_cell(index);
+        state.apply_guess(index, wrong);
+        assert_eq!((state.fails, state.fail), (1, Some(index)));
+        assert_eq!(state.sudoku[index], wrong, "unlike strict mode, the digit stays");
+        assert!(state.shows_wrong(index));
+        state.undo();
+        state.redo();
+        assert_eq!(state.fail, Some(index));
+
+        state.apply_guess(index, state.solved[index]);
+        assert_eq!(state.fail, None);
+        assert!(!state.shows_wrong(index));
+    }
+
+    #[test]
+    fn conflicting_peers_are_outlined_until_the_next_selection() {
+        let mut state = empty_state();
+        // A 5 in the row, one in the column and one in the block of cell 0.
+        for (i, digit) in [(4, 5), (36, 5), (10, 5), (20, 7)] {
+            state.sudoku[i] = digit;
+        }
+        state.sudoku[0] = 5;
+        assert_eq!(state.conflicting_peers(0), vec![4, 10, 36]);
+        assert_eq!(state.conflicting_peers(20), Vec::<usize>::new());
+        assert!(state.has_conflict(0) && !state.has_conflict(20));
+        state.sudoku[0] = 0;
+
+        state.select_cell(0);
+        state.apply_guess(0, 5);
+        assert_eq!(state.fail, Some(0));
+        assert_eq!(state.conflict_peers, vec![4, 10, 36]);
+        state.select_cell(1);
+        assert!(state.conflict_peers.is_empty());
+    }
+
+    #[test]
+    fn freeform_mode_keeps_quiet_about_mistakes() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.settings.freeform = true;
+        let (index, wrong) = wrong_digit(&state, true);
+
+        state.select_cell(index);
+        state.apply_guess(index, wrong);
+        assert_eq!((state.fails, state.fail, state.collision), (0, None, false));
+        assert!(!state.conflicts().contains(&true));
+        assert_eq!((state.flash, state.accuracy()), (None, None));
+        state.undo();
+        state.redo();
+        assert_eq!((state.fail, state.collision), (None, false));
+
+        for i in 0..state.sudoku.len() {
+            if state.sudoku[i] != state.solved[i] {
+                state.apply_guess(i, state.solved[i]);
             }
+        }
+        assert!(state.is_solved() && state.solved_dialog_open);
+    }
+
+    #[test]
+    fn explain_names_the_next_step_until_the_next_edit() {
+        let line =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut state =
+            AppState::from_sudoku(sudoku::Sudoku::from_str_line(line).unwrap(), DEFAULT_DIFFICULTY);
+        state.explain();
+        let explanation = state.explanation.clone().unwrap();
+        let index = explanation.target.unwrap();
+        let (row, col) = (index / 9 + 1, index % 9 + 1);
+        let digit = state.solved[index];
+        assert!(explanation.text.starts_with(&format!("R{row}C{col} must be {digit} (")));
+        assert!(!explanation.cells.is_empty());
+        assert_eq!(state.sudoku[index], 0, "explaining places nothing");
+        assert!(state.assisted && state.hints_used == 1);
+
+        state.apply_guess(index, digit);
+        assert_eq!(state.explanation, None);
+    }
+
+    #[test]
+    fn check_flags_wrong_guesses_until_next_edit() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let mut empty = (0..state.sudoku.len()).filter(|&i| state.sudoku[i] == 0);
+        let (wrong, right) = (empty.next().unwrap(), empty.next().unwrap());
+        // Write directly, as a legal but wrong digit needn't conflict with anything.
+        state.sudoku[wrong] = state.solved[wrong] % 9 + 1;
+        state.sudoku[right] = state.solved[right];
+        let before = state.elapsed_seconds();
+
+        state.check_board();
+        assert!(state.wrong[wrong]);
+        assert_eq!(state.wrong.iter().filter(|&&w| w).count(), 1);
+        assert_eq!(state.checks_used, 1);
+        assert!(state.assisted);
+        assert!(state.elapsed_seconds() >= before + CHECK_PENALTY_SECS);
+
+        state.clear_cell(wrong);
+        assert!(!state.wrong.contains(&true));
+    }
+
+    #[test]
+    fn bookmark_restores_board_and_notes() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let mut empty = (0..state.sudoku.len()).filter(|&i| state.sudoku[i] == 0);
+        let (first, second) = (empty.next().unwrap(), empty.next().unwrap());
+        state.apply_guess(first, state.solved[first]);
+        state.toggle_note(second, 1);
+        state.bookmark();
+        let board = state.sudoku.clone();
+
+        state.apply_guess(second, state.solved[second] % 9 + 1);
+        state.restore_bookmark();
+        assert_eq!(state.sudoku, board);
+        assert!(state.notes[second][0]);
+        assert_eq!(state.voids, board.iter().filter(|&&v| v == 0).count());
+        assert_eq!((state.fail, state.collision), (None, false));
+        assert!(state.undo_stack.is_empty());
+        assert_eq!(state.guesses, 2);
+
+        state.new_game();
+        assert!(state.bookmark.is_none());
+    }
+
+    #[test]
+    fn restart_replays_the_same_puzzle() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let (puzzle, solution) = (state.sudoku.clone(), state.solved.clone());
+        let index = puzzle.iter().position(|&v| v == 0).unwrap();
+        state.apply_guess(index, state.solved[index] % 9 + 1);
+        state.toggle_note(puzzle.iter().rposition(|&v| v == 0).unwrap(), 1);
+        state.check_board();
+
+        state.restart();
+        assert_eq!(state.sudoku, puzzle);
+        assert_eq!(state.solved, solution);
+        assert_eq!((state.fails, state.guesses, state.checks_used), (0, 0, 0));
+        assert!(state.notes.iter().all(|n| !n.contains(&true)));
+        assert!(state.elapsed_seconds() < CHECK_PENALTY_SECS);
+    }
+
+    #[test]
+    fn board_snapshot_includes_player_entries() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let index = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        state.apply_guess(index, state.solved[index]);
+
+        let text = state.board_snapshot().to_string();
+        let digits: Vec<char> = text
+            .lines()
+            .filter(|line| !line.starts_with('-'))
+            .flat_map(|line| line.split_whitespace())
+            .filter(|&cell| cell != "|")
+            .map(|cell| cell.chars().next().unwrap())
+            .collect();
+        let expected: Vec<char> = state.sudoku.iter().map(|&v| sudoku::digit_char(v)).collect();
+        assert_eq!(digits, expected);
+    }
+
+    #[test]
+    fn clock_waits_for_the_first_move() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        // As if the player studied the board for a minute.
+        state.last_tick = Instant::now() - Duration::from_secs(60);
+        state.tick();
+        assert_eq!(state.elapsed_seconds(), 0);
+
+        state.select_cell(0);
+        assert!(state.started);
+        state.last_tick = Instant::now() - Duration::from_secs(1);
+        assert_eq!(state.elapsed_seconds(), 1);
+    }
+
+    #[test]
+    fn suspended_time_is_not_counted() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.started = true;
+        state.active_time = Duration::from_secs(10);
+        // As if the machine slept for an hour since the last tick.
+        state.last_tick = Instant::now() - Duration::from_secs(3600);
+        state.tick();
+        assert_eq!(state.elapsed_seconds(), 10 + MAX_TICK_DELTA.as_secs());
 
-            let block_grid = grid(block_cells, BLOCK_SIDE as i32, BLOCK_SIDE as i32);
-            sudoku_blocks.push(sized_box(block_grid).grid_pos(block_col as i32, block_row as i32));
+        // Paused time doesn't count either.
+        state.toggle_pause();
+        state.last_tick = Instant::now() - Duration::from_secs(60);
+        assert_eq!(state.elapsed_seconds(), 12);
+        state.toggle_pause();
+        assert_eq!(state.elapsed_seconds(), 12);
+    }
+
+    #[test]
+    fn minimap_tells_clues_guesses_and_empty_cells_apart() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        let index = state.sudoku.iter().position(|&v| v == 0).unwrap();
+        assert_eq!(state.fill(clue), Fill::Clue);
+        assert_eq!(state.fill(index), Fill::Empty);
+        state.apply_guess(index, state.solved[index]);
+        assert_eq!(state.fill(index), Fill::Guess);
+    }
+
+    #[test]
+    fn clock_color_follows_best_pace() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        assert_eq!(state.ahead_of_best_pace(), None);
+        state.start_clock();
+        assert_eq!(state.ahead_of_best_pace(), None, "no best time yet");
+
+        // A best time of one second per empty cell.
+        let to_fill = state.voids as u64;
+        state.stats.best_times.insert(state.level, to_fill);
+        let empty: Vec<usize> = (0..state.sudoku.len())
+            .filter(|&i| state.sudoku[i] == 0)
+            .take(10)
+            .collect();
+        for &i in &empty {
+            state.sudoku[i] = state.solved[i];
         }
+        state.recompute_voids_and_maybe_stop_timer();
+
+        state.active_time = Duration::from_secs(5);
+        state.last_tick = Instant::now();
+        assert_eq!(state.ahead_of_best_pace(), Some(true));
+        state.active_time = Duration::from_secs(20);
+        assert_eq!(state.ahead_of_best_pace(), Some(false));
+
+        state.stopped_time = Some(20);
+        assert_eq!(state.ahead_of_best_pace(), None);
     }
 
-    grid(sudoku_blocks, BOARD_BLOCKS as i32, BOARD_BLOCKS as i32).gap(GRID_GAP)
-}
+    #[test]
+    fn highlight_follows_mode() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let clue = state.is_clue.iter().position(|&c| c).unwrap();
+        state.select_cell(clue);
+        let count = |mask: &[bool]| mask.iter().filter(|&&m| m).count();
+        // Row, column and block of a 9×9 board share 21 cells.
+        assert_eq!(state.settings.highlight_mode, HighlightMode::RowColBlock);
+        assert_eq!(count(&state.highlight), 21);
 
-fn app_logic(state: &mut AppState) -> impl WidgetView<AppState> + use<> {
-    let board = build_board(state);
+        state.cycle_highlight_mode();
+        assert_eq!(state.settings.highlight_mode, HighlightMode::SameDigit);
+        assert_eq!(count(&state.highlight), 0);
+        assert!(count(&state.highlight_same) > 0);
 
-    let layout = flex_col((
-        FlexSpacer::Fixed(GAP),
-        info_bar(state),
-        number_grid().flex(1.0),
-        board.flex(9.0),
-    ))
-    .gap(GAP);
+        state.cycle_highlight_mode();
+        assert_eq!(state.settings.highlight_mode, HighlightMode::None);
+        assert_eq!(count(&state.highlight_same), 0);
 
-    // Background task: tick regularly to update the timer label.
-    fork(
-        layout,
-        state.active.then(|| {
-            task(
-                |proxy, _| async move {
-                    let mut interval = time::interval(Duration::from_millis(TIMER_TICK_MS));
-                    loop {
-                        interval.tick().await;
-                        if proxy.message(()).is_err() {
-                            break;
-                        }
-                    }
-                },
-                |_state: &mut AppState, ()| {
-                    // No state mutation needed; re-running the view updates the timer display.
-                },
-            )
-        }),
-    )
-}
+        state.cycle_highlight_mode();
+        assert_eq!(state.settings.highlight_mode, HighlightMode::RowCol);
+        assert_eq!(count(&state.highlight), 17);
+        assert!(count(&state.highlight_same) > 0);
+    }
 
-fn main() -> Result<(), EventLoopError> {
-    let window_options = WindowOptions::new("Sudoku")
-        .with_min_inner_size(LogicalSize::new(600.0, 600.0))
-        .with_initial_inner_size(LogicalSize::new(700.0, 700.0));
+    #[test]
+    fn font_scale_persists_in_settings() {
+        let (mut state, path) = temp_settings("settings.json");
+        state.set_font_scale(1.5);
+        state.new_game();
+        assert_eq!(state.settings.font_scale, 1.5);
+        assert_eq!(Settings::load(&path).font_scale, 1.5);
 
-    let app = Xilem::new_simple(AppState::default(), app_logic, window_options);
+        // Out-of-range values are clamped.
+        state.set_font_scale(10.0);
+        assert_eq!(Settings::load(&path).font_scale, MAX_FONT_SCALE);
+    }
 
-    app.run_in(EventLoop::with_user_event())?;
-    Ok(())
+    #[test]
+    fn preferences_persist_and_shape_the_first_game() {
+        let (mut state, path) = temp_settings("prefs.json");
+        state.cycle_highlight_mode();
+        state.cycle_fail_limit();
+        state.settings.diagonal = true;
+        state.settings.auto_advance = true;
+        state.save_settings();
+
+        let settings = Settings::load(&path);
+        assert_eq!(settings.highlight_mode, HighlightMode::SameDigit);
+        assert_eq!(settings.max_fails, FAIL_LIMIT_PRESETS[1]);
+        assert!(settings.auto_advance && settings.auto_strike);
+
+        let state = AppState::with_settings(DEFAULT_DIFFICULTY, settings);
+        assert_eq!(state.variant, Variant::Diagonal);
+        assert!(state.settings.diagonal);
+
+        // Settings written before these preferences existed keep their defaults.
+        let old: Settings = serde_json::from_str(r#"{"font_scale":1.5}"#).unwrap();
+        assert!(old.auto_strike && !old.strict);
+    }
+
+    #[test]
+    fn window_size_is_remembered_above_the_minimum() {
+        let (mut state, path) = temp_settings("window.json");
+        assert_eq!(
+            state.settings.initial_window_size(),
+            LogicalSize::new(DEFAULT_WINDOW_SIDE, DEFAULT_WINDOW_SIDE)
+        );
+        state.record_window_size(Size::new(812.4, 640.6));
+        // Nothing is written while the window may still be moving.
+        state.save_window_size_if_settled();
+        assert!(!path.exists());
+        state.window_resized = Some(Instant::now() - WINDOW_SAVE_DELAY);
+        state.save_window_size_if_settled();
+        assert_eq!(state.window_resized, None);
+        let settings = Settings::load(&path);
+        assert_eq!(settings.window_size, Some((812.0, 641.0)));
+        assert_eq!(
+            settings.initial_window_size(),
+            LogicalSize::new(812.0, 641.0)
+        );
+
+        // A size from a hand-edited file can't shrink the window below the minimum.
+        state.settings.window_size = Some((300.0, 900.0));
+        assert_eq!(
+            state.settings.initial_window_size(),
+            LogicalSize::new(MIN_WINDOW_SIDE, 900.0)
+        );
+    }
+
+    #[test]
+    fn board_stays_square_in_its_share_of_the_play_area() {
+        // The pad and the gap before it take a tenth of 500 + 4 pixels.
+        let length = 500.0 + GAP_SIZE;
+        // A wide window: the height decides, wherever the pad goes.
+        let wide = Size::new(1200.0, length);
+        assert_eq!(board_extent(wide, false), 450.0);
+        assert_eq!(board_extent(wide, true), length);
+        // A tall window: the width decides.
+        let tall = Size::new(length, 1200.0);
+        assert_eq!(board_extent(tall, false), length);
+        assert_eq!(board_extent(tall, true), 450.0);
+        assert_eq!(board_extent(Size::ZERO, true), 0.0);
+    }
+
+    #[test]
+    fn only_large_boards_zoom() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        state.set_board_zoom(MAX_BOARD_ZOOM + 1.0);
+        assert_eq!(state.settings.board_zoom, MAX_BOARD_ZOOM);
+        assert_eq!(state.board_zoom(), MIN_BOARD_ZOOM);
+
+        let cells = 16 * 16;
+        let board = sudoku::Sudoku(vec![0; cells], vec![0; cells], Variant::Classic);
+        state.start_game(board);
+        assert_eq!(state.board_zoom(), MAX_BOARD_ZOOM);
+        // The number pad follows the board's digits, 1–9 and A–G.
+        assert_eq!(state.remaining_digits().len(), 16);
+        assert_eq!(sudoku::digit_char(16), 'G');
+    }
+
+    #[test]
+    fn glyph_sets_change_only_the_display() {
+        let (mut state, path) = temp_settings("glyphs.json");
+        assert_eq!(state.settings.glyphs.glyph(9), '9');
+        state.cycle_glyphs();
+        assert_eq!(Settings::load(&path).glyphs, GlyphSet::Letters);
+        assert_eq!(
+            (GlyphSet::Letters.glyph(1), GlyphSet::Letters.glyph(16)),
+            ('A', 'P')
+        );
+        assert_eq!(GlyphSet::Shapes.glyph(1), '●');
+        assert_eq!(GlyphSet::Digits.glyph(16), 'G');
+
+        let mut notes = [false; MAX_SIDE];
+        notes[0] = true;
+        notes[2] = true;
+        assert_eq!(
+            notes_digits(&notes, state.geometry, GlyphSet::Letters),
+            "AC"
+        );
+        assert!(notes_text(&notes, state.geometry, GlyphSet::Shapes).starts_with("● \u{2007} ▲"));
+
+        // Every set tells all 16 digits apart.
+        for set in GlyphSet::ALL {
+            let glyphs: std::collections::BTreeSet<char> = (1..=16).map(|d| set.glyph(d)).collect();
+            assert_eq!(glyphs.len(), 16, "{set:?}");
+        }
+    }
+
+    #[test]
+    fn pad_position_cycles_and_persists() {
+        let (mut state, path) = temp_settings("pad.json");
+        assert_eq!(state.settings.pad_position, PadPosition::Top);
+        state.cycle_pad_position();
+        state.cycle_pad_position();
+        assert_eq!(Settings::load(&path).pad_position, PadPosition::Left);
+        assert!(state.settings.pad_position.is_beside());
+        for _ in 0..2 {
+            state.cycle_pad_position();
+        }
+        assert_eq!(Settings::load(&path).pad_position, PadPosition::Top);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timer_retries_failed_ticks() {
+        let live = AtomicBool::new(false);
+        let mut attempts = 0;
+        // Two failures, then a delivery whose reply is dropped, which ends the task.
+        run_timer(
+            |_reply| {
+                attempts += 1;
+                if attempts <= 2 { Err(()) } else { Ok(()) }
+            },
+            &live,
+        )
+        .await;
+        assert_eq!(attempts, 3);
+        assert!(live.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timer_gives_up_on_closed_channel() {
+        let live = AtomicBool::new(true);
+        let mut attempts = 0;
+        run_timer(
+            |_reply| {
+                attempts += 1;
+                Err(())
+            },
+            &live,
+        )
+        .await;
+        assert_eq!(attempts, TICK_SEND_ATTEMPTS);
+        assert!(!live.load(Ordering::Relaxed));
+    }
+
+    /// A solved game without fails, for the statistics tests.
+    fn game(seconds: u64, assisted: bool) -> Completion {
+        Completion {
+            seconds,
+            fails: 0,
+            assisted,
+            score: 0,
+        }
+    }
+
+    #[test]
+    fn worksheet_is_written_with_solutions() {
+        let mut state = AppState::new(DEFAULT_DIFFICULTY);
+        let path = TempFile::new("worksheet.txt");
+        state.worksheet.count = "3".to_string();
+        state.worksheet.path = path.display().to_string();
+        state.generate_worksheet();
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(text.matches("Puzzle ").count(), 3);
+        assert_eq!(text.matches("Solution ").count(), 3);
+        assert!(state.worksheet.message.take().unwrap().starts_with("Wrote 3 puzzles"));
+
+        state.worksheet.count = "0".to_string();
+        state.generate_worksheet();
+        assert!(state.worksheet.message.take().unwrap().starts_with("Enter a count"));
+
+        // IO errors are reported, not raised.
+        state.worksheet.count = "1".to_string();
+        state.worksheet.path = path.join("missing").display().to_string();
+        state.generate_worksheet();
+        assert!(state.worksheet.message.take().unwrap().starts_with("Could not write"));
+    }
+
+    #[test]
+    fn score_rewards_difficulty_and_penalizes_help() {
+        let mut state = AppState::new(3.0);
+        state.stopped_time = Some(100);
+        assert_eq!(state.score(), SCORE_BASE + 3 * SCORE_PER_LEVEL - 100 * SCORE_PER_SECOND);
+
+        state.fails = 2;
+        state.hints_used = 1;
+        state.checks_used = 1;
+        assert_eq!(
+            state.score(),
+            SCORE_BASE + 3 * SCORE_PER_LEVEL
+                - 100 * SCORE_PER_SECOND
+                - 2 * SCORE_PER_FAIL
+                - SCORE_PER_HINT
+                - SCORE_PER_CHECK
+        );
+
+        state.stopped_time = Some(100_000);
+        assert_eq!(state.score(), 0);
+    }
+
+    #[test]
+    fn a_hint_costs_its_penalty_and_no_more() {
+        let mut state = AppState::new(3.0);
+        state.give_hint();
+        assert!(state.revealed.contains(&true));
+        state.stopped_time = Some(0);
+        assert_eq!(state.score(), SCORE_BASE + 3 * SCORE_PER_LEVEL - SCORE_PER_HINT);
+
+        state.reveal_solution();
+        assert_eq!(state.score(), 0);
+    }
+
+    #[test]
+    fn stats_track_best_time_and_streak() {
+        let mut stats = Stats::default();
+        assert!(stats.record(3, game(300, false)));
+        assert!(!stats.record(3, game(400, false)));
+        assert!(stats.record(3, game(200, false)));
+        assert_eq!(stats.best_time(3), Some(200));
+        assert_eq!(stats.streak, 3);
+
+        assert!(!stats.record(3, game(10, true)));
+        assert_eq!(stats.best_time(3), Some(200));
+        assert_eq!(stats.streak, 0);
+        assert_eq!(stats.games_completed, 4);
+    }
+
+    #[test]
+    fn stats_track_daily_puzzles() {
+        let mut stats = Stats::default();
+        assert!(!stats.daily_solved(20260101));
+        stats.record_daily(20260101, game(300, true));
+        assert!(stats.daily_solved(20260101));
+        assert_eq!(stats.daily_best(20260101), None);
+        stats.record_daily(20260101, game(400, false));
+        stats.record_daily(20260101, game(500, false));
+        assert_eq!(stats.daily_best(20260101), Some(400));
+        assert!(!stats.daily_solved(20260102));
+    }
+
+    #[test]
+    fn dates_follow_the_gregorian_calendar() {
+        assert_eq!(date_of_day(0), 19700101);
+        assert_eq!(date_of_day(11017), 20000301);
+        assert_eq!(date_of_day(19782), 20240229);
+        assert_eq!(date_of_day(20818), 20261231);
+        assert!(today() >= 20260101);
+    }
+
+    #[test]
+    fn daily_puzzle_is_the_same_for_everyone_that_day() {
+        let mut state = AppState::new(sudoku::MAX_DIFFICULTY_LEVEL as f64);
+        state.settings.diagonal = true;
+        state.start_daily(20260101);
+        let puzzle = state.sudoku.clone();
+        assert_eq!((state.level, state.daily), (DAILY_LEVEL, Some(20260101)));
+        assert_eq!(state.variant, Variant::Classic, "the player's options don't apply");
+
+        let mut other = AppState::new(DEFAULT_DIFFICULTY);
+        other.start_daily(20260101);
+        assert_eq!(other.sudoku, puzzle);
+        other.start_daily(20260102);
+        assert_ne!(other.sudoku, puzzle);
+
+        state.restart();
+        assert_eq!(state.daily, Some(20260101));
+        state.reveal_solution();
+        assert!(state.stats.daily_solved(20260101));
+        state.new_game();
+        assert_eq!(state.daily, None);
+    }
+
+    #[test]
+    fn stats_keep_recent_games_per_level() {
+        let mut stats = Stats::default();
+        for seconds in 1..=HISTORY_LEN as u64 + 2 {
+            stats.record(3, game(seconds, seconds % 2 == 0));
+        }
+        stats.record(5, game(99, false));
+
+        let recent: Vec<u64> = stats.history(3).map(|game| game.seconds).collect();
+        assert_eq!(recent, (3..=HISTORY_LEN as u64 + 2).collect::<Vec<_>>());
+        assert_eq!(stats.history(5).next(), Some(&game(99, false)));
+        assert_eq!(stats.history(1).count(), 0);
+
+        // Statistics written before the history existed still load.
+        let old: Stats =
+            serde_json::from_str(r#"{"games_completed":1,"streak":1,"best_times":{"3":100}}"#)
+                .unwrap();
+        assert_eq!(old.history(3).count(), 0);
+    }
 }
@@ -0,0 +1,78 @@
+// Sound effects for Xilem Sudoku
+// (c) S. Salewski 2026
+
+//! Short tones for placements, mistakes and a solved board.
+//!
+//! Audio needs the `sound` feature; without it `play` does nothing. The tones
+//! are played by a thread of their own, so the UI never waits for the device.
+
+/// Events that make a sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// A digit was placed: a soft click.
+    Place,
+    /// A guess counted as a mistake: a low buzz.
+    Fail,
+    /// The puzzle was solved: a rising chime.
+    Win,
+}
+
+impl Effect {
+    /// The tones of the effect, as frequency in Hz and length in milliseconds.
+    #[cfg_attr(not(feature = "sound"), allow(dead_code))]
+    fn notes(self) -> &'static [(f32, u64)] {
+        match self {
+            Self::Place => &[(880.0, 30)],
+            Self::Fail => &[(110.0, 250)],
+            Self::Win => &[(523.3, 120), (659.3, 120), (784.0, 120), (1046.5, 300)],
+        }
+    }
+}
+
+/// Queue `effect` for playing; returns at once. Without an audio device, or
+/// without the `sound` feature, nothing is heard.
+#[cfg(feature = "sound")]
+pub fn play(effect: Effect) {
+    use std::sync::OnceLock;
+    use std::sync::mpsc::Sender;
+
+    // Started on first use; `None` if the thread could not be spawned.
+    static PLAYER: OnceLock<Option<Sender<Effect>>> = OnceLock::new();
+    if let Some(player) = PLAYER.get_or_init(start_player) {
+        // A player thread that found no device has ended; that's fine.
+        let _ = player.send(effect);
+    }
+}
+
+#[cfg(not(feature = "sound"))]
+pub fn play(_effect: Effect) {}
+
+/// Spawn the thread that owns the output stream and plays what it is sent.
+#[cfg(feature = "sound")]
+fn start_player() -> Option<std::sync::mpsc::Sender<Effect>> {
+    use rodio::source::{SineWave, Source};
+    use std::time::Duration;
+
+    let (sender, receiver) = std::sync::mpsc::channel::<Effect>();
+    std::thread::Builder::new()
+        .name("sound".into())
+        .spawn(move || {
+            let Ok(stream) = rodio::OutputStreamBuilder::open_default_stream() else {
+                return;
+            };
+            for effect in receiver {
+                // One sink per effect plays its notes in turn, while effects overlap.
+                let sink = rodio::Sink::connect_new(stream.mixer());
+                for &(frequency, ms) in effect.notes() {
+                    sink.append(
+                        SineWave::new(frequency)
+                            .take_duration(Duration::from_millis(ms))
+                            .amplify(0.2),
+                    );
+                }
+                sink.detach();
+            }
+        })
+        .ok()?;
+    Some(sender)
+}
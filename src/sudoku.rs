@@ -2,57 +2,393 @@
 // (c) S. Salewski 2025
 // 25-NOV-2025
 
-use rand::{rng, seq::SliceRandom};
-use rand::RngExt;
+use rand::rngs::StdRng;
+use rand::{Rng, RngExt, SeedableRng, rng, seq::SliceRandom};
 
 use std::fmt;
 
-pub const MAX_DIFFICULTY_LEVEL: u8 = 7; // up to 7*7+8 zeros
+pub const MAX_DIFFICULTY_LEVEL: u8 = 7; // up to 7*7 zeros beyond the easy start
 pub const SUGGESTED_DIFFICULTY_LEVEL: u8 = 3;
 
-const SIDE: usize = 9;
+/// No 9×9 Sudoku with fewer clues has a unique solution.
+pub const MIN_CLUES: usize = 17;
+
+/// Placements after which filling an empty grid starts over; far above what
+/// a 9×9 grid ever needs, so classic (and seeded) generation never restarts.
+const SOLVE_RESTART_BUDGET: u32 = 20_000;
+/// Restarts after which `new_solved` gives up on filling a grid. Every
+/// supported board and variant fills within a handful; running out means the
+/// rules admit no solved grid at all.
+const MAX_SOLVE_RESTARTS: u32 = 100;
+/// Easy starts drawn by one generation pass before giving up on one that can
+/// be mirrored, or solved by logic alone, as the options ask for.
+const MAX_START_ATTEMPTS: u32 = 100;
+/// Fresh solved grids tried by `new_with_clues` and `new_with_pattern` before giving up.
+const MAX_CLUE_ATTEMPTS: u32 = 20;
+/// Fewest clues a difficulty level aims for on the 9×9 board, scaled by cell
+/// count for other sizes. Random removal seldom keeps a unique solution much
+/// below this, so deeper targets would only burn retries.
+const MIN_TARGET_CLUES: usize = 24;
+/// Puzzles generated for one request when clue removal falls short of the
+/// level's target; the one with the most empty cells is kept.
+const GENERATION_ATTEMPTS: u32 = 4;
+
+/// Supported block sides: 4×4, 9×9 and 16×16 boards.
+pub const BLOCK_SIDES: [usize; 3] = [2, 3, 4];
+/// Block side of the classic 9×9 board.
+pub const DEFAULT_BLOCK_SIDE: usize = 3;
+/// Largest board side; digits must fit into a `u16` candidate mask.
+pub const MAX_SIDE: usize = 16;
+
+// Classic 9×9 geometry, which difficulty levels and `MIN_CLUES` refer to.
+const SIDE: usize = DEFAULT_BLOCK_SIDE * DEFAULT_BLOCK_SIDE;
 const CELL_COUNT: usize = SIDE * SIDE;
-const BLOCK_SIDE: usize = 3;
 
-type Row = [i8; SIDE];
-type Col = [i8; SIDE];
-type Block = [i8; SIDE];
+#[cfg(test)]
+thread_local! {
+    /// Makes `is_unique` check classic puzzles by plain scans instead of bit
+    /// masks, so that benchmarks can time generation with either.
+    static UNIQUE_BY_SCAN: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+type Row = Vec<i8>;
+type Col = Vec<i8>;
+type Block = Vec<i8>;
 
-fn shuffled_array_0_to_8() -> [i8; SIDE] {
-    let mut arr = std::array::from_fn(|i| i as i8);
-    arr.shuffle(&mut rng());
+fn shuffled_array_0_to_n<R: Rng + ?Sized>(n: usize, rng: &mut R) -> Vec<i8> {
+    let mut arr: Vec<i8> = (0..n as i8).collect();
+    arr.shuffle(rng);
     arr
 }
 
-fn shuffled_squares() -> [usize; CELL_COUNT] {
-    let mut arr = std::array::from_fn(|i| i);
-    arr.shuffle(&mut rng());
+fn shuffled_squares<R: Rng + ?Sized>(cells: usize, rng: &mut R) -> Vec<usize> {
+    let mut arr: Vec<usize> = (0..cells).collect();
+    arr.shuffle(rng);
     arr
 }
 
+/// Character for a cell value: `.` for empty, `1`–`9`, then `A`–`G` for 10–16.
+pub fn digit_char(v: i8) -> char {
+    match v {
+        0 => '.',
+        1..=9 => char::from(b'0' + v as u8),
+        _ => char::from(b'A' + (v - 10) as u8),
+    }
+}
+
+/// Inverse of `digit_char` for clue digits; letters may be lowercase.
+fn char_digit(ch: char) -> Option<i8> {
+    match ch {
+        '1'..='9' => Some(ch as i8 - b'0' as i8),
+        'A'..='G' => Some(ch as i8 - b'A' as i8 + 10),
+        'a'..='g' => Some(ch as i8 - b'a' as i8 + 10),
+        _ => None,
+    }
+}
+
+fn grid_line(grid: &[i8]) -> String {
+    grid.iter().map(|&v| digit_char(v)).collect()
+}
+
+/// Board geometry: `side`×`side` cells in `block_side`×`block_side` blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Shape {
+    block_side: usize,
+    side: usize,
+    cells: usize,
+}
+
+impl Shape {
+    fn new(block_side: usize) -> Self {
+        assert!(
+            BLOCK_SIDES.contains(&block_side),
+            "unsupported block side {block_side}"
+        );
+        let side = block_side * block_side;
+        Self {
+            block_side,
+            side,
+            cells: side * side,
+        }
+    }
+
+    /// The shape of a grid with `cells` cells, if it is a supported size.
+    fn of(cells: usize) -> Option<Self> {
+        BLOCK_SIDES
+            .into_iter()
+            .find(|&b| b.pow(4) == cells)
+            .map(Self::new)
+    }
+
+    /// Candidate mask with every digit of this board set.
+    fn full_mask(self) -> u16 {
+        ((1u32 << self.side) - 1) as u16
+    }
+
+    /// Row, column and block index of `idx`.
+    #[inline]
+    fn units(self, idx: usize) -> (usize, usize, usize) {
+        let (r, c) = (idx / self.side, idx % self.side);
+        let b = (r / self.block_side) * self.block_side + c / self.block_side;
+        (r, c, b)
+    }
+
+    /// Kind of unit `u`, numbered as in `unit_cells`.
+    fn unit_kind(self, u: usize) -> UnitKind {
+        match u / self.side {
+            0 => UnitKind::Row,
+            1 => UnitKind::Column,
+            2 => UnitKind::Block,
+            _ => UnitKind::Diagonal,
+        }
+    }
+
+    /// Number of units: rows, columns, blocks, and with `diagonal` the two diagonals.
+    fn unit_count(self, diagonal: bool) -> usize {
+        3 * self.side + if diagonal { 2 } else { 0 }
+    }
+
+    /// Cell indices of unit `u`: `side` rows first, then as many columns and
+    /// blocks, then the main diagonal and the anti-diagonal.
+    fn unit_cells(self, u: usize) -> impl Iterator<Item = usize> {
+        let Self {
+            block_side, side, ..
+        } = self;
+        (0..side).map(move |k| {
+            if u < side {
+                u * side + k
+            } else if u < 2 * side {
+                k * side + (u - side)
+            } else if u < 3 * side {
+                let b = u - 2 * side;
+                let origin = (b / block_side) * side * block_side + (b % block_side) * block_side;
+                origin + (k / block_side) * side + k % block_side
+            } else if u == 3 * side {
+                k * side + k
+            } else {
+                k * side + (side - 1 - k)
+            }
+        })
+    }
+
+    /// The row, column and block units containing `idx`, followed by the
+    /// diagonals through it when `diagonal` is set.
+    fn units_of(self, idx: usize, diagonal: bool) -> impl Iterator<Item = usize> {
+        let (r, c, b) = self.units(idx);
+        let side = self.side;
+        let main = (diagonal && r == c).then_some(3 * side);
+        let anti = (diagonal && r + c == side - 1).then_some(3 * side + 1);
+        [r, side + c, 2 * side + b]
+            .into_iter()
+            .chain(main)
+            .chain(anti)
+    }
+}
+
+/// All cells on the main diagonals through `idx` of a board with the given
+/// block side, including `idx` itself; empty for cells off the diagonals.
+pub fn diagonal_cells(block_side: usize, idx: usize) -> impl Iterator<Item = usize> {
+    let shape = Shape::new(block_side);
+    shape
+        .units_of(idx, true)
+        .skip(3)
+        .flat_map(move |u| shape.unit_cells(u))
+}
+
+/// Cells of each unit through `idx`: its row, column and block, followed by
+/// the main diagonals through it when `diagonal` is set.
+pub fn units_through(
+    block_side: usize,
+    idx: usize,
+    diagonal: bool,
+) -> impl Iterator<Item = Vec<usize>> {
+    let shape = Shape::new(block_side);
+    shape
+        .units_of(idx, diagonal)
+        .map(move |u| shape.unit_cells(u).collect())
+}
+
+/// Reasons a puzzle is rejected when read, built from a grid, or solved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SudokuError {
+    /// The grid does not have exactly 16, 81 or 256 cells.
+    BadLength(usize),
+    /// A character that is neither `.`, `0` nor a digit of the board, at the given position.
+    BadChar { pos: usize, ch: char },
+    /// A value that is neither 0 nor a digit of the board, at the given position.
+    BadValue { pos: usize, value: i8 },
+    /// The clue at the given position repeats a digit within its row, column,
+    /// block or diagonal.
+    ClueConflict(usize),
+    /// The clues admit no solution.
+    NoSolution,
+    /// The clues admit more than one solution.
+    MultipleSolutions,
+}
+
+impl fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadLength(n) => write!(f, "expected 16, {CELL_COUNT} or 256 cells, got {n}"),
+            Self::BadChar { pos, ch } => write!(f, "bad character {ch:?} at position {pos}"),
+            Self::BadValue { pos, value } => write!(f, "bad value {value} at position {pos}"),
+            Self::ClueConflict(pos) => write!(f, "clue at position {pos} repeats a digit"),
+            Self::NoSolution => write!(f, "puzzle has no solution"),
+            Self::MultipleSolutions => write!(f, "puzzle has multiple solutions"),
+        }
+    }
+}
+
+impl std::error::Error for SudokuError {}
+
+/// Check that `grid` has a supported size and that no clue repeats a digit
+/// within one of its units; the first such clue in reading order is reported.
+fn check_clues(grid: &[i8], variant: Variant) -> Result<(), SudokuError> {
+    let shape = Shape::of(grid.len()).ok_or(SudokuError::BadLength(grid.len()))?;
+    let diagonal = variant == Variant::Diagonal;
+    let repeats = |idx: usize| {
+        shape.units_of(idx, diagonal).any(|u| {
+            shape
+                .unit_cells(u)
+                .any(|j| j != idx && grid[j] == grid[idx])
+        })
+    };
+    match (0..grid.len()).find(|&idx| grid[idx] != 0 && repeats(idx)) {
+        Some(pos) => Err(SudokuError::ClueConflict(pos)),
+        None => Ok(()),
+    }
+}
+
+/// What generating a puzzle took, as reported by `Sudoku::new_with_meta`.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GenMeta {
+    /// Clues left in the puzzle.
+    pub clues: usize,
+    /// Clue removals tried after the easy start, each costing a uniqueness check.
+    pub attempts: u32,
+    /// Every clue was tried for removal: none can go without losing uniqueness.
+    pub minimal: bool,
+}
+
+/// Rule set a puzzle follows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    /// Every row, column and block holds each digit once.
+    #[default]
+    Classic,
+    /// X-Sudoku: the two main diagonals must hold each digit once as well.
+    Diagonal,
+}
+
+/// Human solving techniques, ordered from easiest to hardest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    LockedCandidates,
+    NakedPair,
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::NakedSingle => "naked single",
+            Self::HiddenSingle => "hidden single",
+            Self::LockedCandidates => "locked candidates",
+            Self::NakedPair => "naked pair",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The kinds of units a digit must appear in once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitKind {
+    Row,
+    Column,
+    Block,
+    Diagonal,
+}
+
+impl fmt::Display for UnitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Row => "row",
+            Self::Column => "column",
+            Self::Block => "block",
+            Self::Diagonal => "diagonal",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One digit placed by the technique solver, with the reasoning behind it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub index: usize,
+    pub value: i8,
+    /// `NakedSingle` or `HiddenSingle`: the techniques that place digits.
+    pub technique: Technique,
+    /// Unit the digit has no other spot in, for a hidden single.
+    pub unit: Option<UnitKind>,
+    /// Hardest elimination needed before the placement, if any.
+    pub after: Option<Technique>,
+    /// Cells the deduction looks at: the unit of a hidden single, or the
+    /// peers ruling out every other digit of a naked single.
+    pub cells: Vec<usize>,
+}
+
+/// Difficulty rating derived from the hardest technique a puzzle needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    /// Naked singles suffice.
+    Easy,
+    /// Needs hidden singles.
+    Medium,
+    /// Needs locked candidates.
+    Hard,
+    /// Needs naked pairs, or can't be solved by the implemented techniques.
+    Expert,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Easy => "Easy",
+            Self::Medium => "Medium",
+            Self::Hard => "Hard",
+            Self::Expert => "Expert",
+        };
+        f.write_str(name)
+    }
+}
+
 /// Tuple struct:
-/// - .0 = puzzle grid (0 = empty)
+/// - .0 = puzzle grid (0 = empty), row by row
 /// - .1 = fully solved grid
-#[derive(Clone, Copy, Debug)]
-pub struct Sudoku(pub [i8; CELL_COUNT], pub [i8; CELL_COUNT]);
+/// - .2 = rule variant both grids obey
+///
+/// The board size follows from the grid length: 16, 81 or 256 cells.
+#[derive(Clone, Debug)]
+pub struct Sudoku(pub Vec<i8>, pub Vec<i8>, pub Variant);
 
 impl fmt::Display for Sudoku {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for r in 0..SIDE {
-            if r != 0 && r % BLOCK_SIDE == 0 {
-                writeln!(f, "------+-------+------")?;
+        let Shape {
+            block_side, side, ..
+        } = self.shape();
+        // "------+-------+------" for 9×9
+        let separator = vec!["-".repeat(2 * block_side - 1); block_side].join("-+-");
+        for r in 0..side {
+            if r != 0 && r % block_side == 0 {
+                writeln!(f, "{separator}")?;
             }
-            for c in 0..SIDE {
-                if c != 0 && c % BLOCK_SIDE == 0 {
+            for c in 0..side {
+                if c != 0 && c % block_side == 0 {
                     write!(f, "| ")?;
                 }
-                let v = self.0[r * SIDE + c];
-                let ch = if v == 0 {
-                    '.'
-                } else {
-                    char::from(b'0' + v as u8)
-                };
-                write!(f, "{ch} ")?;
+                write!(f, "{} ", digit_char(self.0[r * side + c]))?;
             }
             writeln!(f)?;
         }
@@ -61,40 +397,62 @@ impl fmt::Display for Sudoku {
 }
 
 impl Sudoku {
+    fn shape(&self) -> Shape {
+        Shape::of(self.0.len()).expect("grid has a supported size")
+    }
+
+    /// Side length of a block, e.g. 3 for the classic 9×9 board.
+    pub fn block_side(&self) -> usize {
+        self.shape().block_side
+    }
+
     // Check that this Sudoku is a complete, valid solution:
     // - no zeros
-    // - each row/col/block contains 1..=9 exactly once
+    // - each row/col/block contains 1..=side exactly once
+    // - for the diagonal variant, so does each main diagonal
     #[allow(dead_code)]
     fn is_valid(&self) -> bool {
-        // helper: check array is exactly {1..=9}
-        fn is_1_to_9(a: &Row) -> bool {
-            let mut v = *a; // copy
+        let Shape {
+            block_side, side, ..
+        } = self.shape();
+        // helper: check array is exactly {1..=side}
+        let is_1_to_n = |a: &Row| -> bool {
+            let mut v = a.clone(); // copy
             v.sort(); // sort in-place
-            v == [1, 2, 3, 4, 5, 6, 7, 8, 9]
-        }
+            v.into_iter().eq(1..=side as i8)
+        };
 
         if self.0.contains(&0) {
             return false;
         }
 
         // all rows
-        for r in 0..SIDE {
-            if !is_1_to_9(&self.row(r)) {
+        for r in 0..side {
+            if !is_1_to_n(&self.row(r)) {
                 return false;
             }
         }
 
         // all cols
-        for c in 0..SIDE {
-            if !is_1_to_9(&self.col(c)) {
+        for c in 0..side {
+            if !is_1_to_n(&self.col(c)) {
                 return false;
             }
         }
 
-        // all 3x3 blocks
-        for br in 0..BLOCK_SIDE {
-            for bc in 0..BLOCK_SIDE {
-                if !is_1_to_9(&self.block(br, bc)) {
+        // all blocks
+        for br in 0..block_side {
+            for bc in 0..block_side {
+                if !is_1_to_n(&self.block(br, bc)) {
+                    return false;
+                }
+            }
+        }
+
+        // both diagonals
+        if self.2 == Variant::Diagonal {
+            for d in 0..2 {
+                if !is_1_to_n(&self.diagonal(d)) {
                     return false;
                 }
             }
@@ -103,51 +461,62 @@ impl Sudoku {
     }
 
     fn row(&self, n: usize) -> Row {
-        assert!(n < SIDE);
-        let start = n * SIDE;
-        let mut row = [0i8; SIDE];
-        row.copy_from_slice(&self.0[start..start + SIDE]);
-        row
+        let side = self.shape().side;
+        assert!(n < side);
+        let start = n * side;
+        self.0[start..start + side].to_vec()
     }
 
     fn col(&self, n: usize) -> Col {
-        assert!(n < SIDE);
-        let mut col = [0i8; SIDE];
-        for (r, cell) in col.iter_mut().enumerate() {
-            *cell = self.0[r * SIDE + n];
-        }
+        let side = self.shape().side;
+        assert!(n < side);
+        (0..side).map(|r| self.0[r * side + n]).collect()
+    }
 
-        col
+    /// Main diagonal for `n == 0`, anti-diagonal for `n == 1`.
+    #[allow(dead_code)]
+    fn diagonal(&self, n: usize) -> Row {
+        assert!(n < 2);
+        let shape = self.shape();
+        shape
+            .unit_cells(3 * shape.side + n)
+            .map(|i| self.0[i])
+            .collect()
     }
 
-    fn set_col(&mut self, n: usize, vals: &Col) {
-        assert!(n < SIDE);
+    fn set_col(&mut self, n: usize, vals: &[i8]) {
+        let side = self.shape().side;
+        assert!(n < side);
         for (r, v) in vals.iter().enumerate() {
-            self.0[r * SIDE + n] = *v;
+            self.0[r * side + n] = *v;
         }
     }
 
     fn block(&self, br: usize, bc: usize) -> Block {
-        assert!(br < BLOCK_SIDE && bc < BLOCK_SIDE);
-        let mut blk = [0i8; SIDE];
-        let (r0, c0) = (br * BLOCK_SIDE, bc * BLOCK_SIDE);
-        let mut k = 0;
-        for r in 0..BLOCK_SIDE {
-            for c in 0..BLOCK_SIDE {
-                blk[k] = self.0[(r0 + r) * SIDE + (c0 + c)];
-                k += 1;
+        let Shape {
+            block_side, side, ..
+        } = self.shape();
+        assert!(br < block_side && bc < block_side);
+        let mut blk = Vec::with_capacity(side);
+        let (r0, c0) = (br * block_side, bc * block_side);
+        for r in 0..block_side {
+            for c in 0..block_side {
+                blk.push(self.0[(r0 + r) * side + (c0 + c)]);
             }
         }
         blk
     }
 
-    fn set_block(&mut self, br: usize, bc: usize, vals: &Block) {
-        assert!(br < BLOCK_SIDE && bc < BLOCK_SIDE);
-        let (r0, c0) = (br * BLOCK_SIDE, bc * BLOCK_SIDE);
+    fn set_block(&mut self, br: usize, bc: usize, vals: &[i8]) {
+        let Shape {
+            block_side, side, ..
+        } = self.shape();
+        assert!(br < block_side && bc < block_side);
+        let (r0, c0) = (br * block_side, bc * block_side);
         let mut k = 0;
-        for r in 0..BLOCK_SIDE {
-            for c in 0..BLOCK_SIDE {
-                self.0[(r0 + r) * SIDE + (c0 + c)] = vals[k];
+        for r in 0..block_side {
+            for c in 0..block_side {
+                self.0[(r0 + r) * side + (c0 + c)] = vals[k];
                 k += 1;
             }
         }
@@ -155,198 +524,942 @@ impl Sudoku {
 
     #[allow(dead_code)]
     fn print(&self) {
-        for i in 0..CELL_COUNT {
-            if i == 3 * SIDE || i == 6 * SIDE {
-                println!("--- --- ---");
+        let Shape {
+            block_side, side, ..
+        } = self.shape();
+        for (i, &v) in self.0.iter().enumerate() {
+            let (r, c) = (i / side, i % side);
+            if c == 0 && r != 0 && r % block_side == 0 {
+                println!("{}", vec!["-".repeat(block_side); block_side].join(" "));
             }
-            print!("{}", self.0[i]);
-            let c = i % SIDE;
-            if c == 2 || c == 5 {
+            print!("{}", digit_char(v));
+            if c % block_side == block_side - 1 && c != side - 1 {
                 print!("|");
             }
-            if c == SIDE - 1 {
+            if c == side - 1 {
                 println!();
             }
         }
     }
 
-    /// Check if `value` can be placed at `idx` without violating Sudoku rules.
-    fn can_place(&self, idx: usize, value: i8) -> bool {
-        let r = idx / SIDE;
-        let c = idx % SIDE;
-
-        // Row
-        let row_start = r * SIDE;
-        for offset in 0..SIDE {
-            if self.0[row_start + offset] == value {
-                return false;
-            }
+    /// Fill all empty cells of the puzzle grid `.0` under the rules of `.2`.
+    ///
+    /// Fails, leaving the grid unchanged, if the clues conflict or have no
+    /// completion. With several solutions, the first in ascending digit order
+    /// is taken; use `is_unique` to rule that out first.
+    #[allow(dead_code)]
+    pub fn solve(&mut self) -> Result<(), SudokuError> {
+        check_clues(&self.0, self.2)?;
+        let mut masks = Masks::new(&self.0, self.2).expect("clues checked above");
+        if self.backtrack(&mut masks, u32::MAX, Frame::ascending, |_| true) {
+            Ok(())
+        } else {
+            Err(SudokuError::NoSolution)
         }
+    }
 
-        // Column
-        for row in 0..SIDE {
-            if self.0[row * SIDE + c] == value {
-                return false;
+    /// Fill all empty cells with a random valid completion; false if there is none.
+    fn solve_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool {
+        self.solve_within(rng, u32::MAX)
+    }
+
+    /// Like `solve_with`, but give up (returning false) after `budget` placements.
+    fn solve_within<R: Rng + ?Sized>(&mut self, rng: &mut R, budget: u32) -> bool {
+        let Some(mut masks) = Masks::new(&self.0, self.2) else {
+            return false;
+        };
+        let side = masks.shape.side;
+        let shuffled = || {
+            let mut order = Frame::ascending();
+            order[..side].shuffle(rng);
+            order
+        };
+        self.backtrack(&mut masks, budget, shuffled, |_| true)
+    }
+
+    /// The empty cell with the fewest candidates, or `None` if the grid is full.
+    ///
+    /// Branching there prunes far better than plain cell order, which can take
+    /// seconds per grid once the diagonal constraints come into play.
+    fn most_constrained(&self, masks: &Masks) -> Option<usize> {
+        (0..self.0.len())
+            .filter(|&i| self.0[i] == 0)
+            .min_by_key(|&i| masks.free(i).count_ones())
+    }
+
+    /// Depth-first search over the empty cells, kept on an explicit stack so
+    /// hard boards can't exhaust the call stack.
+    ///
+    /// Each entered cell tries its digits in the sequence returned by `order`.
+    /// `on_solution` is called with every completed grid and returns true to
+    /// stop there, which leaves the grid filled and returns true. Otherwise the
+    /// grid is restored and false is returned once the search is exhausted or
+    /// `budget` placements have been made.
+    fn backtrack(
+        &mut self,
+        masks: &mut Masks,
+        mut budget: u32,
+        mut order: impl FnMut() -> [i8; MAX_SIDE],
+        mut on_solution: impl FnMut(&[i8]) -> bool,
+    ) -> bool {
+        let mut stack: Vec<Frame> = Vec::new();
+        loop {
+            // Enter the next cell, or report the completed grid.
+            match self.most_constrained(masks) {
+                Some(idx) => stack.push(Frame {
+                    idx,
+                    untried: masks.free(idx),
+                    order: order(),
+                }),
+                None if on_solution(&self.0) => return true,
+                None => {}
             }
-        }
 
-        // Block
-        let br = r / BLOCK_SIDE;
-        let bc = c / BLOCK_SIDE;
-        let block_origin = br * SIDE * BLOCK_SIDE + bc * BLOCK_SIDE;
-        for br in 0..BLOCK_SIDE {
-            for bc in 0..BLOCK_SIDE {
-                if self.0[block_origin + bc + br * SIDE] == value {
+            // Place the next untried digit, backing out of exhausted cells.
+            loop {
+                let Some(frame) = stack.last_mut() else {
                     return false;
+                };
+                let idx = frame.idx;
+                if self.0[idx] != 0 {
+                    masks.toggle(idx, self.0[idx]);
+                    self.0[idx] = 0;
+                }
+                match frame.next_digit() {
+                    Some(_) if budget == 0 => {
+                        self.unwind(&stack, masks);
+                        return false;
+                    }
+                    Some(v) => {
+                        budget -= 1;
+                        self.0[idx] = v;
+                        masks.toggle(idx, v);
+                        break;
+                    }
+                    None => {
+                        stack.pop();
+                    }
                 }
             }
         }
+    }
 
-        true
+    /// Clear the digits placed for `stack` when a search is abandoned.
+    fn unwind(&mut self, stack: &[Frame], masks: &mut Masks) {
+        for frame in stack {
+            let v = self.0[frame.idx];
+            if v != 0 {
+                masks.toggle(frame.idx, v);
+                self.0[frame.idx] = 0;
+            }
+        }
     }
 
-    fn solve_from(&mut self, idx: usize) -> bool {
-        if idx == CELL_COUNT {
-            return true;
+    // Internal: count solutions of the remaining empty cells, up to `limit`.
+    // Returns a number in 0..=limit.
+    fn count_solutions_from(&mut self, limit: u32, masks: &mut Masks) -> u32 {
+        if limit == 0 {
+            return 0;
+        }
+        let mut count = 0;
+        // For counting, randomness isn't required; ascending digits are fine.
+        self.backtrack(masks, u32::MAX, Frame::ascending, |_| {
+            count += 1;
+            count >= limit // early stop
+        });
+        count
+    }
+
+    /// Number of solutions of the puzzle grid `.0`, counting no further than
+    /// `limit`. Conflicting clues count as no solution. The grid is unchanged.
+    pub fn solution_count(&self, limit: u32) -> u32 {
+        let Some(mut masks) = Masks::new(&self.0, self.2) else {
+            return 0; // conflicting clues
+        };
+        let mut copy = self.clone(); // work on a copy so the original isn't modified
+        copy.count_solutions_from(limit, &mut masks)
+    }
+
+    /// Up to `limit` distinct solutions of the puzzle grid `.0`, in the order
+    /// an ascending-digit search finds them. Conflicting clues give none.
+    #[cfg_attr(not(feature = "solution-explorer"), allow(dead_code))]
+    pub fn solutions(&self, limit: usize) -> Vec<Vec<i8>> {
+        let mut found = Vec::new();
+        let Some(mut masks) = Masks::new(&self.0, self.2) else {
+            return found;
+        };
+        if limit == 0 {
+            return found;
         }
-        if self.0[idx] != 0 {
-            return self.solve_from(idx + 1);
+        let mut copy = self.clone();
+        copy.backtrack(&mut masks, u32::MAX, Frame::ascending, |grid| {
+            found.push(grid.to_vec());
+            found.len() >= limit
+        });
+        found
+    }
+
+    /// Whether the puzzle grid `.0` has exactly one solution.
+    pub fn is_unique(&self) -> bool {
+        #[cfg(test)]
+        if UNIQUE_BY_SCAN.get() {
+            return tests::count_solutions_by_scan(&mut self.0.clone(), 0, 2) == 1;
         }
+        self.solution_count(2) == 1
+    }
 
-        let mut digits = [1i8, 2, 3, 4, 5, 6, 7, 8, 9];
-        digits.shuffle(&mut rng());
+    /// Parse a puzzle in the common one-line format: all cells row by row,
+    /// digits `1`–`9` (then `A`–`G` on 16×16 boards) for clues and `.` or `0`
+    /// for empty cells. The board size follows from the length: 16, 81 or 256.
+    ///
+    /// The puzzle must have exactly one solution, which is stored in `.1`.
+    /// The format carries no variant, so the puzzle is read as classic.
+    pub fn from_str_line(line: &str) -> Result<Self, SudokuError> {
+        let line = line.trim();
+        let len = line.chars().count();
+        let Some(shape) = Shape::of(len) else {
+            return Err(SudokuError::BadLength(len));
+        };
 
-        for &v in &digits {
-            if self.can_place(idx, v) {
-                self.0[idx] = v;
-                if self.solve_from(idx + 1) {
-                    return true;
-                }
-                self.0[idx] = 0;
-            }
+        let mut clues = vec![0; len];
+        for (pos, ch) in line.chars().enumerate() {
+            clues[pos] = match ch {
+                '.' | '0' => 0,
+                _ => match char_digit(ch) {
+                    Some(v) if v as usize <= shape.side => v,
+                    _ => return Err(SudokuError::BadChar { pos, ch }),
+                },
+            };
         }
-        false
+        Self::from_clues(clues, Variant::Classic)
     }
 
-    // Internal: count solutions from `idx`, up to `limit`.
-    // Returns a number in 0..=limit.
-    fn count_solutions_from(&mut self, idx: usize, limit: u32) -> u32 {
-        if limit == 0 {
-            return 0;
+    /// Build a classic 9×9 puzzle from its cells, row by row with 0 for empty
+    /// cells. The clues must not conflict and must have exactly one solution,
+    /// which is stored in `.1`.
+    #[allow(dead_code)]
+    pub fn from_grid(puzzle: [i8; CELL_COUNT]) -> Result<Self, SudokuError> {
+        Self::from_clues(puzzle.to_vec(), Variant::Classic)
+    }
+
+    /// Check and solve the clues of a puzzle; `clues` must have 16, 81 or 256 cells.
+    fn from_clues(clues: Vec<i8>, variant: Variant) -> Result<Self, SudokuError> {
+        let side = Shape::of(clues.len()).expect("a supported board size").side;
+        if let Some(pos) = clues.iter().position(|&v| !(0..=side as i8).contains(&v)) {
+            return Err(SudokuError::BadValue {
+                pos,
+                value: clues[pos],
+            });
         }
-        if idx == CELL_COUNT {
-            return 1; // one complete solution
+        check_clues(&clues, variant)?;
+
+        let mut s = Self(clues.clone(), clues, variant);
+        match s.solution_count(2) {
+            0 => return Err(SudokuError::NoSolution),
+            1 => {}
+            _ => return Err(SudokuError::MultipleSolutions),
         }
-        if self.0[idx] != 0 {
-            return self.count_solutions_from(idx + 1, limit);
+
+        let mut solved = s.clone();
+        solved.solve_with(&mut rng());
+        s.1 = solved.0;
+        Ok(s)
+    }
+
+    /// Format the puzzle grid in the one-line format read by `from_str_line`,
+    /// using `.` for empty cells.
+    pub fn to_str_line(&self) -> String {
+        grid_line(&self.0)
+    }
+
+    /// Format the solved grid in the one-line format.
+    #[allow(dead_code)]
+    pub fn solution_str_line(&self) -> String {
+        grid_line(&self.1)
+    }
+
+    /// Rate the puzzle by the hardest human technique needed to solve it,
+    /// always applying the easiest technique that makes progress.
+    pub fn rate(&self) -> Difficulty {
+        match self.hardest_technique() {
+            Some(Technique::NakedSingle) => Difficulty::Easy,
+            Some(Technique::HiddenSingle) => Difficulty::Medium,
+            Some(Technique::LockedCandidates) => Difficulty::Hard,
+            Some(Technique::NakedPair) | None => Difficulty::Expert,
         }
+    }
 
-        let mut count = 0;
-        // For counting, randomness isn't required; 1..=9 is fine.
-        for v in 1i8..=9 {
-            if self.can_place(idx, v) {
-                self.0[idx] = v;
-                let found = self.count_solutions_from(idx + 1, limit - count);
-                count += found;
-                self.0[idx] = 0; // backtrack
-                if count >= limit {
-                    break; // early stop
-                }
-            }
+    /// Whether the implemented techniques solve the puzzle without guessing.
+    /// Such a puzzle has exactly one solution, as every step is forced.
+    pub fn solvable_by_logic(&self) -> bool {
+        self.hardest_technique().is_some()
+    }
+
+    /// Hardest technique needed to solve the puzzle, or `None` when the
+    /// implemented techniques get stuck.
+    fn hardest_technique(&self) -> Option<Technique> {
+        let mut board = Candidates::new(&self.0, self.2);
+        let mut hardest = Technique::NakedSingle;
+
+        while !board.is_solved() {
+            let technique = if board.naked_single().is_some() {
+                Technique::NakedSingle
+            } else if board.hidden_single().is_some() {
+                Technique::HiddenSingle
+            } else if board.locked_candidates() {
+                Technique::LockedCandidates
+            } else if board.naked_pair() {
+                Technique::NakedPair
+            } else {
+                return None;
+            };
+            hardest = hardest.max(technique);
         }
-        count
+        Some(hardest)
     }
 
-    // Public: count solutions of the *current puzzle*, but cap at `limit`.
-    fn count_solutions(&self, limit: u32) -> u32 {
-        let mut copy = *self; // work on a copy so the original isn't modified
-        copy.count_solutions_from(0, limit)
+    /// The next digit the implemented techniques place in the puzzle grid
+    /// `.0`, trying the easiest technique first as `rate` does; `None` once the
+    /// grid is full or the techniques get stuck.
+    pub fn next_step(&self) -> Option<Step> {
+        let mut board = Candidates::new(&self.0, self.2);
+        let mut after = None;
+        while !board.is_solved() {
+            if let Some(step) = board.naked_single().or_else(|| board.hidden_single()) {
+                return Some(Step { after, ..step });
+            }
+            let technique = if board.locked_candidates() {
+                Technique::LockedCandidates
+            } else if board.naked_pair() {
+                Technique::NakedPair
+            } else {
+                return None;
+            };
+            after = after.max(Some(technique));
+        }
+        None
     }
 
-    // Does this puzzle have exactly one solution?
-    fn has_unique_solution(&self) -> bool {
-        self.count_solutions(2) == 1
+    /// Whether the puzzle grid `.0` is still acceptable: uniquely solvable,
+    /// and with `logic_only` solvable by the implemented techniques as well.
+    fn keeps_solvable(&self, logic_only: bool) -> bool {
+        if logic_only {
+            self.solvable_by_logic()
+        } else {
+            self.is_unique()
+        }
     }
 
     /// Generate a fully solved Sudoku grid.
-    fn new_solved() -> Self {
-        let mut s = Self([0; CELL_COUNT], [0; CELL_COUNT]);
-        s.solve_from(0);
-        s
+    ///
+    /// Filling an empty 16×16 grid usually takes milliseconds, but an unlucky
+    /// early choice can send the search into a dead end for minutes. Starting
+    /// over with fresh random choices is far cheaper than backing out of it.
+    ///
+    /// Returns `None` if no grid was found within `MAX_SOLVE_RESTARTS` tries.
+    fn new_solved<R: Rng + ?Sized>(shape: Shape, variant: Variant, rng: &mut R) -> Option<Self> {
+        (0..MAX_SOLVE_RESTARTS).find_map(|_| {
+            let mut s = Self(vec![0; shape.cells], vec![0; shape.cells], variant);
+            s.solve_within(rng, SOLVE_RESTART_BUDGET).then_some(s)
+        })
     }
 
-    /// Generate a new Sudoku with the given difficulty level.
+    /// Generate a new Sudoku with the given difficulty level on a board of
+    /// `block_side`×`block_side` blocks, which must be one of `BLOCK_SIDES`.
     ///
     /// The exact difficulty model is heuristic:
     /// - level 0: very easy, roughly one zero per row/column.
     /// - level > 0: progressively more zeros, while preserving uniqueness.
-    pub fn new(level: u8) -> Self {
-        let mut s = Self::new_solved();
+    ///
+    /// The puzzle is unique under the rules of `variant`. Returns `None` if
+    /// no solved grid could be found to start from.
+    pub fn new(level: u8, block_side: usize, variant: Variant) -> Option<Self> {
+        Self::new_with_meta(level, block_side, variant).map(|(s, _)| s)
+    }
+
+    /// Like `new`, and also report how generation went.
+    pub fn new_with_meta(
+        level: u8,
+        block_side: usize,
+        variant: Variant,
+    ) -> Option<(Self, GenMeta)> {
+        Self::generate(
+            level,
+            Shape::new(block_side),
+            false,
+            false,
+            variant,
+            &mut rng(),
+        )
+    }
+
+    /// Like `new`, but clues are removed in 180° rotationally symmetric pairs,
+    /// as in most printed puzzles.
+    pub fn new_symmetric(level: u8, block_side: usize, variant: Variant) -> Option<Self> {
+        Self::generate(
+            level,
+            Shape::new(block_side),
+            true,
+            false,
+            variant,
+            &mut rng(),
+        )
+        .map(|(s, _)| s)
+    }
+
+    /// Like `new`, or `new_symmetric` if `symmetric` is set, but the puzzle can
+    /// be solved by the techniques `rate` knows, without any trial and error.
+    /// Clues that only guessing could do without stay, so puzzles come out
+    /// with somewhat more clues at the top levels.
+    pub fn new_logic_only(
+        level: u8,
+        block_side: usize,
+        variant: Variant,
+        symmetric: bool,
+    ) -> Option<Self> {
+        let shape = Shape::new(block_side);
+        Self::generate(level, shape, symmetric, true, variant, &mut rng()).map(|(s, _)| s)
+    }
+
+    /// Like `new` for classic 9×9 puzzles, but reproducible: the same `level`
+    /// and `seed` always yield the same puzzle and solution.
+    pub fn new_seeded(level: u8, seed: u64) -> Option<Self> {
+        Self::generate(
+            level,
+            Shape::new(DEFAULT_BLOCK_SIDE),
+            false,
+            false,
+            Variant::Classic,
+            &mut StdRng::seed_from_u64(seed),
+        )
+        .map(|(s, _)| s)
+    }
+
+    /// Generate a classic 9×9 puzzle with exactly `target_clues` clues and a
+    /// unique solution.
+    ///
+    /// Returns `None` for targets below `MIN_CLUES` or above the cell count, or
+    /// when no such puzzle was found within a bounded number of attempts.
+    pub fn new_with_clues(target_clues: usize) -> Option<Self> {
+        if !(MIN_CLUES..=CELL_COUNT).contains(&target_clues) {
+            return None;
+        }
+
+        let rng = &mut rng();
+        let shape = Shape::new(DEFAULT_BLOCK_SIDE);
+        for _ in 0..MAX_CLUE_ATTEMPTS {
+            let mut s = Self::new_solved(shape, Variant::Classic, rng)?;
+            s.1 = s.0.clone();
+
+            let mut clues = CELL_COUNT;
+            for pos in shuffled_squares(CELL_COUNT, rng) {
+                if clues == target_clues {
+                    break;
+                }
+                let val = s.0[pos];
+                s.0[pos] = 0;
+                if s.is_unique() {
+                    clues -= 1;
+                } else {
+                    s.0[pos] = val;
+                }
+            }
+            if clues == target_clues {
+                return Some(s);
+            }
+        }
+        None
+    }
+
+    /// Generate a classic 9×9 puzzle whose clues sit exactly where `mask` is
+    /// true, for puzzles with patterned givens such as a diagonal or a cross.
+    ///
+    /// Returns `None` if the pattern has fewer than `MIN_CLUES` cells, or when
+    /// none of a bounded number of solved grids gives a unique puzzle with it;
+    /// some patterns never do.
+    #[allow(dead_code)]
+    pub fn new_with_pattern(mask: &[bool; CELL_COUNT]) -> Option<Self> {
+        if mask.iter().filter(|&&given| given).count() < MIN_CLUES {
+            return None;
+        }
+
+        let rng = &mut rng();
+        let shape = Shape::new(DEFAULT_BLOCK_SIDE);
+        for _ in 0..MAX_CLUE_ATTEMPTS {
+            let mut s = Self::new_solved(shape, Variant::Classic, rng)?;
+            s.1 = s.0.clone();
+            for (val, &given) in s.0.iter_mut().zip(mask) {
+                if !given {
+                    *val = 0;
+                }
+            }
+            if s.is_unique() {
+                return Some(s);
+            }
+        }
+        None
+    }
+
+    fn generate<R: Rng + ?Sized>(
+        level: u8,
+        shape: Shape,
+        symmetric: bool,
+        logic_only: bool,
+        variant: Variant,
+        rng: &mut R,
+    ) -> Option<(Self, GenMeta)> {
+        // A removal pass can run out of clues it may take before reaching the
+        // target, which would make the top levels indistinguishable; retry then.
+        let mut attempts = 0;
+        let mut best: Option<(Self, GenMeta, usize)> = None;
+        for _ in 0..GENERATION_ATTEMPTS {
+            let (s, meta, shortfall) =
+                Self::remove_clues(level, shape, symmetric, logic_only, variant, rng)?;
+            attempts += meta.attempts;
+            if best
+                .as_ref()
+                .is_none_or(|&(_, _, fewest)| shortfall < fewest)
+            {
+                best = Some((s, meta, shortfall));
+            }
+            if shortfall == 0 {
+                break;
+            }
+        }
+        let (s, meta, _) = best.expect("at least one generation attempt");
+        Some((s, GenMeta { attempts, ..meta }))
+    }
+
+    /// One generation pass: an easy start, thinned towards the level's target.
+    /// Also returns by how many empty cells the target was missed.
+    fn remove_clues<R: Rng + ?Sized>(
+        level: u8,
+        shape: Shape,
+        symmetric: bool,
+        logic_only: bool,
+        variant: Variant,
+        rng: &mut R,
+    ) -> Option<(Self, GenMeta, usize)> {
+        let mut start = None;
+        for _ in 0..MAX_START_ATTEMPTS {
+            let mut s = Self::easy_start(level, shape, variant, rng)?;
+            if (!symmetric || s.mirror_zeros()) && (!logic_only || s.solvable_by_logic()) {
+                start = Some(s);
+                break;
+            }
+        }
+        let mut s = start?;
+
+        // Now we have an easy start; remove a few more clues to increase difficulty.
+        // Levels are tuned for 9×9. Scale by side rather than cell count: proving
+        // uniqueness of a 16×16 grid gets slow long before 155 extra zeros.
+        let mut more_zeros = level as usize * 7 * shape.side / SIDE;
+        let voids = s.0.iter().filter(|&&v| v == 0).count();
+        let max_voids = shape.cells - shape.cells * MIN_TARGET_CLUES / CELL_COUNT;
+        more_zeros = more_zeros.min(max_voids.saturating_sub(voids));
+        // Every puzzle, level 0 included, leaves at least a row's worth of cells
+        // to fill. The easy start already has one zero per row; this makes the
+        // floor explicit rather than a side effect of how it places them.
+        more_zeros = more_zeros.max(shape.side.saturating_sub(voids));
+
+        let mut attempts = 0;
+        let mut tried_all = true;
+        let positions = shuffled_squares(shape.cells, rng);
+        for pos in positions {
+            if more_zeros == 0 {
+                tried_all = false;
+                break;
+            }
+            // In symmetric mode cells are removed in 180° pairs; the center cell pairs with itself.
+            let pair = if symmetric {
+                [pos, shape.cells - 1 - pos]
+            } else {
+                [pos, pos]
+            };
+            let vals = pair.map(|i| s.0[i]);
+            if vals[0] != 0 {
+                attempts += 1;
+                for i in pair {
+                    s.0[i] = 0;
+                }
+                if !s.keeps_solvable(logic_only) {
+                    // Revert if uniqueness (or solvability by logic) is lost.
+                    for (i, v) in pair.into_iter().zip(vals) {
+                        s.0[i] = v;
+                    }
+                } else {
+                    more_zeros = more_zeros.saturating_sub(if pair[0] == pair[1] { 1 } else { 2 });
+                }
+            }
+        }
+
+        let meta = GenMeta {
+            clues: s.0.iter().filter(|&&v| v != 0).count(),
+            attempts,
+            // Removing clues never makes a failed removal succeed later, so once
+            // every clue was tried singly, none can go. Pairs prove nothing about
+            // single clues. Nor does logic-only removal prove anything about
+            // uniqueness.
+            minimal: tried_all && !symmetric && !logic_only,
+        };
+        Some((s, meta, more_zeros))
+    }
+
+    /// Remove every clue that isn't needed for a unique solution, trying the
+    /// clues in random order. The result is minimal: taking away any further
+    /// clue admits a second solution.
+    ///
+    /// Clues go one at a time, so a symmetric puzzle may lose its symmetry.
+    /// Meant for boards up to 9×9: on 16×16 boards every uniqueness check of
+    /// the thinning grid gets slow, and this can take minutes.
+    pub fn minimize(&mut self) {
+        self.minimize_with(&mut rng(), false);
+    }
+
+    /// Like `minimize`, but only remove clues the puzzle stays solvable by
+    /// logic without; see `new_logic_only`.
+    pub fn minimize_logic_only(&mut self) {
+        self.minimize_with(&mut rng(), true);
+    }
+
+    fn minimize_with<R: Rng + ?Sized>(&mut self, rng: &mut R, logic_only: bool) {
+        for pos in shuffled_squares(self.0.len(), rng) {
+            let val = self.0[pos];
+            if val == 0 {
+                continue;
+            }
+            self.0[pos] = 0;
+            if !self.keeps_solvable(logic_only) {
+                self.0[pos] = val;
+            }
+        }
+    }
+
+    /// A solved grid with a few zeros in every row, column and block.
+    fn easy_start<R: Rng + ?Sized>(
+        level: u8,
+        shape: Shape,
+        variant: Variant,
+        rng: &mut R,
+    ) -> Option<Self> {
+        let Shape {
+            block_side, side, ..
+        } = shape;
+        let mut s = Self::new_solved(shape, variant, rng)?;
         // Save fully solved version.
-        s.1 = s.0;
+        s.1 = s.0.clone();
 
         if level == 0 {
             // Ensure only one zero per row and column -- very easy start.
-            let a = shuffled_array_0_to_8();
+            let a = shuffled_array_0_to_n(side, rng);
             for (row, &col_idx) in a.iter().enumerate() {
-                s.0[row * SIDE + col_idx as usize] = 0;
+                s.0[row * side + col_idx as usize] = 0;
             }
         } else {
             // Allow multiple (or zero) zeros per column.
-            for row in 0..SIDE {
-                let col = rng().random_range(0..SIDE);
-                s.0[row * SIDE + col] = 0;
+            for row in 0..side {
+                let col = rng.random_range(0..side);
+                s.0[row * side + col] = 0;
             }
         }
 
         // Ensure every column has at least one zero.
-        for col in 0..SIDE {
+        for col in 0..side {
             let mut c = s.col(col);
             if !c.contains(&0) {
-                let r = rng().random_range(0..SIDE);
+                let r = rng.random_range(0..side);
                 c[r] = 0;
                 s.set_col(col, &c);
             }
         }
 
         // Ensure every block has at least one zero.
-        for br in 0..BLOCK_SIDE {
-            for bc in 0..BLOCK_SIDE {
+        for br in 0..block_side {
+            for bc in 0..block_side {
                 let mut b = s.block(br, bc);
                 if !b.contains(&0) {
-                    let idx = rng().random_range(0..SIDE);
+                    let idx = rng.random_range(0..side);
                     b[idx] = 0;
                     s.set_block(br, bc, &b);
                 }
             }
         }
 
-        // Now we have an easy start; remove a few more clues to increase difficulty.
-        let mut more_zeros = level * 7;
+        Some(s)
+    }
 
-        let positions = shuffled_squares();
-        for pos in positions {
-            if more_zeros == 0 {
-                break;
+    /// Zero the 180° mirror of every empty cell; false if that breaks uniqueness.
+    fn mirror_zeros(&mut self) -> bool {
+        let cells = self.0.len();
+        for i in 0..cells {
+            if self.0[i] == 0 {
+                self.0[cells - 1 - i] = 0;
             }
-            let val = s.0[pos];
-            if val != 0 {
-                s.0[pos] = 0;
-                if !s.has_unique_solution() {
-                    // Revert if uniqueness is lost.
-                    s.0[pos] = val;
-                } else {
-                    more_zeros -= 1;
+        }
+        self.is_unique()
+    }
+}
+
+// --- Technique solver -------------------------------------------------------------------------
+
+/// Candidate bit for `digit` (bit 0 = digit 1).
+#[inline]
+fn bit(digit: i8) -> u16 {
+    1 << (digit - 1)
+}
+
+/// One level of `Sudoku::backtrack`: the cell being filled, the candidates
+/// not tried there yet, and the order to try them in.
+struct Frame {
+    idx: usize,
+    untried: u16,
+    order: [i8; MAX_SIDE],
+}
+
+impl Frame {
+    /// All digits in ascending order.
+    fn ascending() -> [i8; MAX_SIDE] {
+        std::array::from_fn(|i| i as i8 + 1)
+    }
+
+    /// Take the next candidate in `order`, if any is left.
+    fn next_digit(&mut self) -> Option<i8> {
+        let v = self
+            .order
+            .iter()
+            .copied()
+            .find(|&v| self.untried & bit(v) != 0)?;
+        self.untried &= !bit(v);
+        Some(v)
+    }
+}
+
+/// Digits already used per row, column and block, as candidate bit masks.
+struct Masks {
+    shape: Shape,
+    rows: [u16; MAX_SIDE],
+    cols: [u16; MAX_SIDE],
+    blocks: [u16; MAX_SIDE],
+    /// Main and anti-diagonal, tracked only for `Variant::Diagonal`.
+    diagonals: Option<[u16; 2]>,
+}
+
+impl Masks {
+    /// Collect the masks of `grid`; `None` if a digit repeats within a unit.
+    fn new(grid: &[i8], variant: Variant) -> Option<Self> {
+        let mut masks = Self {
+            shape: Shape::of(grid.len())?,
+            rows: [0; MAX_SIDE],
+            cols: [0; MAX_SIDE],
+            blocks: [0; MAX_SIDE],
+            diagonals: (variant == Variant::Diagonal).then_some([0; 2]),
+        };
+        for (idx, &v) in grid.iter().enumerate() {
+            if v != 0 {
+                if masks.free(idx) & bit(v) == 0 {
+                    return None;
                 }
+                masks.toggle(idx, v);
+            }
+        }
+        Some(masks)
+    }
+
+    /// Digits that can still go into `idx`.
+    #[inline]
+    fn free(&self, idx: usize) -> u16 {
+        let (r, c, b) = self.shape.units(idx);
+        let mut used = self.rows[r] | self.cols[c] | self.blocks[b];
+        if let Some(diagonals) = &self.diagonals {
+            if r == c {
+                used |= diagonals[0];
+            }
+            if r + c == self.shape.side - 1 {
+                used |= diagonals[1];
+            }
+        }
+        !used & self.shape.full_mask()
+    }
+
+    /// Mark `v` as used at `idx`, or unmark it again when backtracking.
+    #[inline]
+    fn toggle(&mut self, idx: usize, v: i8) {
+        let (r, c, b) = self.shape.units(idx);
+        self.rows[r] ^= bit(v);
+        self.cols[c] ^= bit(v);
+        self.blocks[b] ^= bit(v);
+        if let Some(diagonals) = &mut self.diagonals {
+            if r == c {
+                diagonals[0] ^= bit(v);
+            }
+            if r + c == self.shape.side - 1 {
+                diagonals[1] ^= bit(v);
             }
         }
-        s
+    }
+}
+
+/// A grid with pencil-mark candidates, solved step by step with human techniques.
+struct Candidates {
+    shape: Shape,
+    grid: Vec<i8>,
+    /// Candidate mask per empty cell; 0 for filled cells.
+    cand: Vec<u16>,
+    /// Whether the diagonals are units as well.
+    diagonal: bool,
+}
+
+impl Candidates {
+    fn new(grid: &[i8], variant: Variant) -> Self {
+        let shape = Shape::of(grid.len()).expect("grid has a supported size");
+        let mut board = Self {
+            shape,
+            grid: vec![0; shape.cells],
+            cand: vec![shape.full_mask(); shape.cells],
+            diagonal: variant == Variant::Diagonal,
+        };
+        for (idx, &v) in grid.iter().enumerate() {
+            if v != 0 {
+                board.place(idx, v);
+            }
+        }
+        board
+    }
+
+    fn is_solved(&self) -> bool {
+        !self.grid.contains(&0)
+    }
+
+    fn unit_count(&self) -> usize {
+        self.shape.unit_count(self.diagonal)
+    }
+
+    fn units_of(&self, idx: usize) -> impl Iterator<Item = usize> + use<> {
+        self.shape.units_of(idx, self.diagonal)
+    }
+
+    fn place(&mut self, idx: usize, v: i8) {
+        self.grid[idx] = v;
+        self.cand[idx] = 0;
+        for u in self.units_of(idx) {
+            for i in self.shape.unit_cells(u) {
+                self.cand[i] &= !bit(v);
+            }
+        }
+    }
+
+    /// Fill a cell that has only one candidate left.
+    fn naked_single(&mut self) -> Option<Step> {
+        let idx = (0..self.shape.cells)
+            .find(|&idx| self.grid[idx] == 0 && self.cand[idx].count_ones() == 1)?;
+        let value = self.cand[idx].trailing_zeros() as i8 + 1;
+        let mut cells: Vec<usize> = self
+            .units_of(idx)
+            .flat_map(|u| self.shape.unit_cells(u))
+            .filter(|&i| i != idx)
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+        self.place(idx, value);
+        Some(Step {
+            index: idx,
+            value,
+            technique: Technique::NakedSingle,
+            unit: None,
+            after: None,
+            cells,
+        })
+    }
+
+    /// Fill the only cell of a unit that can still take some digit.
+    fn hidden_single(&mut self) -> Option<Step> {
+        for u in 0..self.unit_count() {
+            for v in 1..=self.shape.side as i8 {
+                let mut spots = self
+                    .shape
+                    .unit_cells(u)
+                    .filter(|&i| self.cand[i] & bit(v) != 0);
+                if let (Some(idx), None) = (spots.next(), spots.next()) {
+                    self.place(idx, v);
+                    return Some(Step {
+                        index: idx,
+                        value: v,
+                        technique: Technique::HiddenSingle,
+                        unit: Some(self.shape.unit_kind(u)),
+                        after: None,
+                        cells: self.shape.unit_cells(u).collect(),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Remove `mask` from every cell of unit `u` not in `keep`; true if anything changed.
+    fn eliminate(&mut self, u: usize, mask: u16, keep: impl Fn(usize) -> bool) -> bool {
+        let mut changed = false;
+        for i in self.shape.unit_cells(u) {
+            if !keep(i) && self.cand[i] & mask != 0 {
+                self.cand[i] &= !mask;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Pointing and claiming: when a digit's spots in one unit all lie in a
+    /// second unit, the digit can be removed from the rest of that second unit.
+    fn locked_candidates(&mut self) -> bool {
+        let (shape, diagonal) = (self.shape, self.diagonal);
+        for u in 0..self.unit_count() {
+            for v in 1..=shape.side as i8 {
+                let spots: Vec<usize> = shape
+                    .unit_cells(u)
+                    .filter(|&i| self.cand[i] & bit(v) != 0)
+                    .collect();
+                let Some(&first) = spots.first() else {
+                    continue;
+                };
+                for other in self.units_of(first) {
+                    if other != u
+                        && spots
+                            .iter()
+                            .all(|&i| shape.units_of(i, diagonal).any(|w| w == other))
+                        && self.eliminate(other, bit(v), |i| {
+                            shape.units_of(i, diagonal).any(|w| w == u)
+                        })
+                    {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Two cells of a unit sharing the same two candidates claim both digits.
+    fn naked_pair(&mut self) -> bool {
+        for u in 0..self.unit_count() {
+            let cells: Vec<usize> = self.shape.unit_cells(u).collect();
+            for (n, &a) in cells.iter().enumerate() {
+                let mask = self.cand[a];
+                if mask.count_ones() != 2 {
+                    continue;
+                }
+                for &b in &cells[n + 1..] {
+                    if self.cand[b] == mask && self.eliminate(u, mask, |i| i == a || i == b) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
     }
 }
 
 #[allow(dead_code)]
 fn main_demo() {
-    let s = Sudoku::new(1);
+    let s = Sudoku::new(1, DEFAULT_BLOCK_SIDE, Variant::Classic).expect("classic board");
     s.print();
     println!("{:?}", s.row(0));
     println!("{:?}", s.col(0));
@@ -357,12 +1470,453 @@ fn main_demo() {
 mod tests {
     use super::*;
 
+    const BLOCK_SIDE: usize = DEFAULT_BLOCK_SIDE;
+
     #[test]
     fn generated_sudoku_is_valid() {
-        let s = Sudoku::new_solved();
-        assert!(
-            s.is_valid(),
-            "Generated Sudoku is not a valid solution:\n{s}"
+        for seed in 0..20 {
+            let s = Sudoku::new_solved(
+                Shape::new(BLOCK_SIDE),
+                Variant::Classic,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .unwrap();
+            assert!(
+                s.is_valid(),
+                "Generated Sudoku (seed {seed}) is not a valid solution:\n{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn solved_grids_fill_every_size_and_variant() {
+        let rng = &mut StdRng::seed_from_u64(7);
+        for block_side in BLOCK_SIDES {
+            for variant in [Variant::Classic, Variant::Diagonal] {
+                let s = Sudoku::new_solved(Shape::new(block_side), variant, rng)
+                    .expect("an empty board always has a solution");
+                assert!(s.is_valid(), "{block_side} {variant:?}:\n{s}");
+            }
+        }
+
+        // Puzzles of every size and variant start from such a grid.
+        for block_side in BLOCK_SIDES {
+            for variant in [Variant::Classic, Variant::Diagonal] {
+                let s = Sudoku::new(0, block_side, variant).expect("generated");
+                let solved = Sudoku(s.1.clone(), s.1.clone(), variant);
+                assert!(solved.is_valid(), "{block_side} {variant:?}:\n{solved}");
+            }
+        }
+    }
+
+    #[test]
+    fn generated_puzzles_are_uniquely_solvable() {
+        // Randomized, so cover each level several times; cheap enough for every run.
+        for level in 0..=MAX_DIFFICULTY_LEVEL {
+            for _ in 0..10 {
+                let s = Sudoku::new(level, BLOCK_SIDE, Variant::Classic).unwrap();
+                assert_eq!(s.solution_count(2), 1, "level {level}:\n{s}");
+
+                let solution = Sudoku(s.1.clone(), s.1.clone(), s.2);
+                assert!(
+                    solution.is_valid(),
+                    "level {level}: bad solution\n{solution}"
+                );
+                assert!(
+                    s.0.iter()
+                        .zip(&s.1)
+                        .all(|(&clue, &v)| clue == 0 || clue == v),
+                    "level {level}: clue contradicts solution\n{s}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rates_by_required_technique() {
+        // Solvable with naked singles alone.
+        let easy =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        assert_eq!(
+            Sudoku::from_str_line(easy).unwrap().rate(),
+            Difficulty::Easy
+        );
+
+        // Arto Inkala's "hardest Sudoku" needs more than the implemented techniques.
+        let hard =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        assert_eq!(
+            Sudoku::from_str_line(hard).unwrap().rate(),
+            Difficulty::Expert
+        );
+    }
+
+    #[test]
+    fn next_step_explains_a_forced_digit() {
+        // One cell short of the solution: a naked single, ruled by all its peers.
+        let line =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let s = Sudoku::from_str_line(line).unwrap();
+        let mut almost = Sudoku(s.1.clone(), s.1.clone(), Variant::Classic);
+        almost.0[40] = 0;
+        let step = almost.next_step().unwrap();
+        assert_eq!((step.index, step.value), (40, s.1[40]));
+        assert_eq!(
+            (step.technique, step.unit, step.after),
+            (Technique::NakedSingle, None, None)
+        );
+        assert_eq!(step.cells.len(), 20);
+
+        // Every step on the way matches the solution and empties one cell less.
+        let mut board = s.clone();
+        while let Some(step) = board.next_step() {
+            assert_eq!(board.0[step.index], 0);
+            assert_eq!(step.value, s.1[step.index]);
+            assert!(step.cells.len() >= SIDE);
+            assert_eq!(
+                step.unit.is_some(),
+                step.technique == Technique::HiddenSingle
+            );
+            board.0[step.index] = step.value;
+        }
+        assert_eq!(board.0, s.1);
+    }
+
+    #[test]
+    fn symmetric_puzzles_are_symmetric_and_unique() {
+        for level in [0, SUGGESTED_DIFFICULTY_LEVEL, MAX_DIFFICULTY_LEVEL] {
+            let s = Sudoku::new_symmetric(level, BLOCK_SIDE, Variant::Classic).unwrap();
+            for i in 0..CELL_COUNT {
+                assert_eq!(
+                    s.0[i] == 0,
+                    s.0[CELL_COUNT - 1 - i] == 0,
+                    "asymmetric:\n{s}"
+                );
+            }
+            assert!(s.is_unique());
+        }
+    }
+
+    #[test]
+    fn diagonal_puzzles_obey_diagonal_rules() {
+        for level in [0, SUGGESTED_DIFFICULTY_LEVEL, MAX_DIFFICULTY_LEVEL] {
+            let s = Sudoku::new(level, BLOCK_SIDE, Variant::Diagonal).unwrap();
+            let solved = Sudoku(s.1.clone(), s.1.clone(), Variant::Diagonal);
+            assert!(solved.is_valid(), "diagonals repeat a digit:\n{solved}");
+            assert!(s.is_unique());
+            assert!(s.0.iter().zip(&s.1).all(|(&p, &v)| p == 0 || p == v));
+        }
+    }
+
+    #[test]
+    fn other_board_sizes_are_valid_and_unique() {
+        for block_side in [2, 4] {
+            let s = Sudoku::new(SUGGESTED_DIFFICULTY_LEVEL, block_side, Variant::Classic).unwrap();
+            assert_eq!(s.block_side(), block_side);
+            assert_eq!(s.0.len(), block_side.pow(4));
+            let solved = Sudoku(s.1.clone(), s.1.clone(), Variant::Classic);
+            assert!(solved.is_valid(), "invalid solution:\n{solved}");
+            assert!(s.is_unique());
+            assert!(s.0.contains(&0));
+
+            let parsed = Sudoku::from_str_line(&s.to_str_line()).unwrap();
+            assert_eq!(parsed.1, s.1);
+        }
+    }
+
+    /// Solution counter using plain row/column/block scans, as a baseline for the bit masks.
+    pub(super) fn count_solutions_by_scan(grid: &mut [i8], idx: usize, limit: u32) -> u32 {
+        if idx == CELL_COUNT {
+            return 1;
+        }
+        if grid[idx] != 0 {
+            return count_solutions_by_scan(grid, idx + 1, limit);
+        }
+        let (r, c) = (idx / SIDE, idx % SIDE);
+        let origin = (r / BLOCK_SIDE) * SIDE * BLOCK_SIDE + (c / BLOCK_SIDE) * BLOCK_SIDE;
+        let mut count = 0;
+        for v in 1i8..=9 {
+            let used = (0..SIDE).any(|k| {
+                grid[r * SIDE + k] == v
+                    || grid[k * SIDE + c] == v
+                    || grid[origin + (k / BLOCK_SIDE) * SIDE + k % BLOCK_SIDE] == v
+            });
+            if !used {
+                grid[idx] = v;
+                count += count_solutions_by_scan(grid, idx + 1, limit - count);
+                grid[idx] = 0;
+                if count >= limit {
+                    break;
+                }
+            }
+        }
+        count
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_uniqueness_check() {
+        use std::time::Instant;
+
+        // Generation runs the uniqueness check after every clue it removes.
+        let generate = |by_scan: bool| {
+            UNIQUE_BY_SCAN.set(by_scan);
+            let start = Instant::now();
+            let puzzles: Vec<Vec<i8>> = (0..10)
+                .map(|seed| Sudoku::new_seeded(MAX_DIFFICULTY_LEVEL, seed).unwrap().0)
+                .collect();
+            (start.elapsed(), puzzles)
+        };
+        let (scan, by_scan) = generate(true);
+        let (masks, by_masks) = generate(false);
+
+        // Both checkers agree on every removal, so the seeds give the same puzzles.
+        assert_eq!(by_scan, by_masks);
+        println!(
+            "10 puzzles at level {MAX_DIFFICULTY_LEVEL}: uniqueness by scan {scan:?}, \
+             by bit masks {masks:?}"
+        );
+    }
+
+    #[test]
+    fn empty_cells_stay_within_band_per_level() {
+        for level in 0..=MAX_DIFFICULTY_LEVEL {
+            for seed in 0..10 {
+                let s = Sudoku::new_seeded(level, seed).unwrap();
+                let voids = s.0.iter().filter(|&&v| v == 0).count();
+                // The easy start empties one cell per row, plus at most one per
+                // column and block; each level then takes 7 more, up to the
+                // clue floor. Retries get the top level to within a cell of it:
+                // level 0 has 9–27 voids, level 3 30–48, level 6 51–57 and level 7 56–57.
+                let cap = CELL_COUNT - MIN_TARGET_CLUES;
+                let extra = 7 * level as usize;
+                let band = (SIDE + extra).min(cap - 1)..=(3 * SIDE + extra).min(cap);
+                assert!(band.contains(&voids), "level {level}: {voids} voids");
+            }
+        }
+    }
+
+    #[test]
+    fn reports_generation_metadata() {
+        let (s, meta) =
+            Sudoku::new_with_meta(MAX_DIFFICULTY_LEVEL, DEFAULT_BLOCK_SIDE, Variant::Classic)
+                .unwrap();
+        assert_eq!(meta.clues, s.0.iter().filter(|&&v| v != 0).count());
+        assert!(meta.attempts as usize >= CELL_COUNT - meta.clues - 3 * SIDE);
+        if meta.minimal {
+            let mut minimized = s.clone();
+            minimized.minimize();
+            assert_eq!(minimized.0, s.0);
+        }
+    }
+
+    #[test]
+    fn logic_only_puzzles_are_solved_without_guessing() {
+        for level in [0, SUGGESTED_DIFFICULTY_LEVEL, MAX_DIFFICULTY_LEVEL] {
+            for symmetric in [false, true] {
+                let mut s =
+                    Sudoku::new_logic_only(level, BLOCK_SIDE, Variant::Classic, symmetric).unwrap();
+                assert!(s.solvable_by_logic(), "level {level}:\n{s}");
+                assert!(s.is_unique());
+                s.minimize_logic_only();
+                assert!(s.solvable_by_logic(), "level {level}, minimized:\n{s}");
+            }
+        }
+
+        // Arto Inkala's puzzle is unique, but defeats the implemented techniques.
+        let hard =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let hard = Sudoku::from_str_line(hard).unwrap();
+        assert!(hard.is_unique() && !hard.solvable_by_logic());
+    }
+
+    #[test]
+    fn generates_requested_clue_count() {
+        assert!(Sudoku::new_with_clues(MIN_CLUES - 1).is_none());
+
+        let s = Sudoku::new_with_clues(30).unwrap();
+        assert_eq!(s.0.iter().filter(|&&v| v != 0).count(), 30);
+        assert!(s.is_unique());
+    }
+
+    #[test]
+    fn generates_patterned_puzzles() {
+        // Everything but the two diagonals: plenty of givens for a unique puzzle.
+        let mask: [bool; CELL_COUNT] =
+            std::array::from_fn(|i| i / SIDE != i % SIDE && i / SIDE + i % SIDE != SIDE - 1);
+        let s = Sudoku::new_with_pattern(&mask).unwrap();
+        assert!(s.0.iter().zip(&mask).all(|(&v, &given)| (v != 0) == given));
+        assert!(s.is_unique());
+
+        // Too few givens, and givens confined to the top band, can't work.
+        let few: [bool; CELL_COUNT] = std::array::from_fn(|i| i < MIN_CLUES - 1);
+        assert!(Sudoku::new_with_pattern(&few).is_none());
+        let band: [bool; CELL_COUNT] = std::array::from_fn(|i| i < 3 * SIDE);
+        assert!(Sudoku::new_with_pattern(&band).is_none());
+    }
+
+    #[test]
+    fn seeded_generation_is_reproducible() {
+        let a = Sudoku::new_seeded(SUGGESTED_DIFFICULTY_LEVEL, 42).unwrap();
+        let b = Sudoku::new_seeded(SUGGESTED_DIFFICULTY_LEVEL, 42).unwrap();
+        assert_eq!(a.0, b.0);
+        assert_eq!(a.1, b.1);
+    }
+
+    #[test]
+    fn solves_hard_17_clue_puzzle() {
+        let puzzle =
+            "000000010400000000020000000000050407008000300001090000300400200050100000000806000";
+        let solution =
+            "693784512487512936125963874932651487568247391741398625319475268856129743274836159";
+        let mut s = Sudoku::from_str_line(puzzle).unwrap();
+        assert_eq!(s.0.iter().filter(|&&v| v != 0).count(), 17);
+        assert_eq!(s.solve(), Ok(()));
+        assert_eq!(s.to_str_line(), solution);
+        assert_eq!(s.solution_str_line(), solution);
+    }
+
+    #[test]
+    fn builds_puzzle_from_grid() {
+        let line =
+            "000000010400000000020000000000050407008000300001090000300400200050100000000806000";
+        let mut grid = [0; CELL_COUNT];
+        for (cell, ch) in grid.iter_mut().zip(line.chars()) {
+            *cell = ch.to_digit(10).unwrap() as i8;
+        }
+        let s = Sudoku::from_grid(grid).unwrap();
+        assert_eq!(s.to_str_line(), line.replace('0', "."));
+        assert!(Sudoku(s.1.clone(), s.1.clone(), Variant::Classic).is_valid());
+
+        let mut bad = grid;
+        bad[0] = 10;
+        assert_eq!(
+            Sudoku::from_grid(bad).err(),
+            Some(SudokuError::BadValue { pos: 0, value: 10 })
+        );
+        // A 1 in the corner repeats the clue 1 further along the first row.
+        let mut conflict = grid;
+        conflict[0] = 1;
+        assert_eq!(
+            Sudoku::from_grid(conflict).err(),
+            Some(SudokuError::ClueConflict(0))
+        );
+        // Seventeen clues are the fewest possible, so none of them can go.
+        let mut open = grid;
+        open[7] = 0;
+        assert_eq!(
+            Sudoku::from_grid(open).err(),
+            Some(SudokuError::MultipleSolutions)
+        );
+    }
+
+    #[test]
+    fn solver_api_handles_open_and_conflicting_grids() {
+        let empty = Sudoku(vec![0; 81], vec![0; 81], Variant::Classic);
+        assert_eq!(empty.solution_count(5), 5);
+        assert!(!empty.is_unique());
+
+        let mut open = empty.clone();
+        assert_eq!(open.solve(), Ok(()));
+        assert!(open.is_valid());
+
+        // Two 1s in the first row.
+        let mut conflicting = empty.clone();
+        conflicting.0[0] = 1;
+        conflicting.0[1] = 1;
+        assert_eq!(conflicting.solution_count(2), 0);
+        assert_eq!(conflicting.solve(), Err(SudokuError::ClueConflict(0)));
+        assert_eq!(conflicting.0.iter().filter(|&&v| v != 0).count(), 2);
+
+        // The first row lacks only a 9, which the last column already has.
+        let mut stuck = empty.clone();
+        for c in 0..8 {
+            stuck.0[c] = c as i8 + 1;
+        }
+        stuck.0[9 + 8] = 9;
+        assert_eq!(stuck.solve(), Err(SudokuError::NoSolution));
+        assert_eq!(stuck.0.iter().filter(|&&v| v != 0).count(), 9);
+
+        let mut short = Sudoku(vec![0; 80], Vec::new(), Variant::Classic);
+        let err: Box<dyn std::error::Error> = short.solve().unwrap_err().into();
+        assert_eq!(err.to_string(), "expected 16, 81 or 256 cells, got 80");
+    }
+
+    #[test]
+    fn solutions_lists_distinct_complete_grids() {
+        let empty = Sudoku(vec![0; 81], vec![0; 81], Variant::Classic);
+        let found = empty.solutions(5);
+        assert_eq!(found.len(), 5);
+        for (i, grid) in found.iter().enumerate() {
+            assert!(Sudoku(grid.clone(), Vec::new(), Variant::Classic).is_valid());
+            assert!(!found[..i].contains(grid));
+        }
+
+        let s = Sudoku::new_seeded(MAX_DIFFICULTY_LEVEL, 3).unwrap();
+        assert_eq!(s.solutions(5), vec![s.1.clone()]);
+
+        let mut conflicting = empty.clone();
+        conflicting.0[0] = 1;
+        conflicting.0[1] = 1;
+        assert!(conflicting.solutions(5).is_empty());
+    }
+
+    #[test]
+    fn minimized_puzzles_are_minimal() {
+        for level in [0, MAX_DIFFICULTY_LEVEL] {
+            let mut s = Sudoku::new(level, BLOCK_SIDE, Variant::Classic).unwrap();
+            let clues = s.0.iter().filter(|&&v| v != 0).count();
+            s.minimize();
+            assert!(s.is_unique());
+            assert!(s.0.iter().filter(|&&v| v != 0).count() <= clues);
+            for pos in 0..s.0.len() {
+                if s.0[pos] != 0 {
+                    let mut fewer = s.clone();
+                    fewer.0[pos] = 0;
+                    assert!(!fewer.is_unique(), "clue at {pos} is redundant");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_puzzle_line() {
+        let line =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let s = Sudoku::from_str_line(line).unwrap();
+        assert_eq!(s.0[0], 5);
+        assert_eq!(s.to_str_line(), line);
+        assert_eq!(
+            Sudoku::from_str_line(&s.solution_str_line()).unwrap().0,
+            s.1
+        );
+        assert_eq!(s.0[2], 0);
+        assert!(Sudoku(s.1.clone(), s.1.clone(), Variant::Classic).is_valid());
+
+        assert_eq!(
+            Sudoku::from_str_line("123").err(),
+            Some(SudokuError::BadLength(3))
+        );
+        let bad = line.replacen('.', "x", 1);
+        assert_eq!(
+            Sudoku::from_str_line(&bad).err(),
+            Some(SudokuError::BadChar { pos: 2, ch: 'x' })
+        );
+        // Letters are only digits on 16×16 boards.
+        let letter = line.replacen('.', "A", 1);
+        assert_eq!(
+            Sudoku::from_str_line(&letter).err(),
+            Some(SudokuError::BadChar { pos: 2, ch: 'A' })
+        );
+        assert_eq!(
+            Sudoku::from_str_line(&".".repeat(CELL_COUNT)).err(),
+            Some(SudokuError::MultipleSolutions)
+        );
+        let conflict = format!("11{}", &line[2..]);
+        assert_eq!(
+            Sudoku::from_str_line(&conflict).err(),
+            Some(SudokuError::ClueConflict(0))
         );
     }
 }